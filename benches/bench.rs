@@ -1,8 +1,32 @@
+use alga::general::AbstractGroup;
+use alga::general::AbstractLoop;
 use alga::general::AbstractMagma;
 use alga::general::AbstractMonoid;
+use alga::general::AbstractQuasigroup;
 use alga::general::AbstractSemigroup;
 use alga::general::Identity;
 use alga::general::Operator;
+use alga::general::TwoSidedInverse;
+use criterion::measurement::WallTime;
+use num_traits::Zero;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+use std::ops::Add;
+use swag::bounded_disorder::BoundedDisorder;
+use swag::daba::queue::Deque;
+use swag::daba::{Item, DABA};
+use swag::daba_lite::DABALite;
+use swag::flat_fat::ra_fifo::RA;
+use swag::flat_fat::ra_multi::MultiRA;
+use swag::ioa::IOA;
+use swag::slick_deque::{SlickDequeInv, SlickDequeNonInv};
+use swag::slide_side::SlideSide;
+use swag::two_stacks::TwoStacks;
+use swag::two_stacks_lite::TwoStacksLite;
+use swag::FifoWindow;
+use swag::MultiWindow;
+use swag::TimeWindow;
 use {criterion::*, swag::fiba::FIBA};
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 struct Value(i32);
@@ -10,7 +34,23 @@ struct Value(i32);
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
 struct Time(i64);
 
-#[derive(Copy, Clone)]
+impl Add for Time {
+    type Output = Time;
+    fn add(self, other: Time) -> Time {
+        Time(self.0 + other.0)
+    }
+}
+
+impl Zero for Time {
+    fn zero() -> Time {
+        Time(0)
+    }
+    fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
 struct BinOp;
 
 impl Operator for BinOp {
@@ -31,8 +71,85 @@ impl AbstractMagma<BinOp> for Value {
     }
 }
 
+impl TwoSidedInverse<BinOp> for Value {
+    fn two_sided_inverse(&self) -> Value {
+        Value(-self.0)
+    }
+}
+
 impl AbstractSemigroup<BinOp> for Value {}
 impl AbstractMonoid<BinOp> for Value {}
+impl AbstractQuasigroup<BinOp> for Value {}
+impl AbstractLoop<BinOp> for Value {}
+impl AbstractGroup<BinOp> for Value {}
+
+#[derive(Copy, Clone)]
+struct Max;
+
+impl Operator for Max {
+    fn operator_token() -> Max {
+        Max
+    }
+}
+
+impl Identity<Max> for Value {
+    fn identity() -> Value {
+        Value(i32::MIN)
+    }
+}
+
+impl AbstractMagma<Max> for Value {
+    fn operate(&self, other: &Self) -> Self {
+        Value(self.0.max(other.0))
+    }
+}
+
+impl AbstractSemigroup<Max> for Value {}
+impl AbstractMonoid<Max> for Value {}
+
+// Bloom filter with a bitset of size 2^14
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Bloom(Box<[u64; 256]>);
+
+#[derive(Copy, Clone, Debug)]
+struct Union;
+
+impl Bloom {
+    fn new(v: i32) -> Bloom {
+        let mut bits = Box::new([0; 256]);
+        let hash = (v as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        for k in 0..3 {
+            let bit = (hash >> (k * 14)) & 0x3FFF;
+            bits[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+        Bloom(bits)
+    }
+}
+
+impl Operator for Union {
+    fn operator_token() -> Union {
+        Union
+    }
+}
+
+impl Identity<Union> for Bloom {
+    fn identity() -> Bloom {
+        Bloom(Box::new([0; 256]))
+    }
+}
+
+impl AbstractMagma<Union> for Bloom {
+    fn operate(&self, other: &Self) -> Self {
+        let mut bits = self.0.clone();
+        for (a, b) in bits.iter_mut().zip(other.0.iter()) {
+            *a |= b;
+        }
+        Bloom(bits)
+    }
+}
+
+impl AbstractSemigroup<Union> for Bloom {}
+impl AbstractMonoid<Union> for Bloom {}
 
 /// We implemented both OoO SWAG variants in C++: the baseline classic B-tree augmented with
 /// aggregates and the finger B-tree aggregator (FiBA). We present experiments with competitive min-
@@ -85,6 +202,8 @@ criterion_group!(
               experiment_5_real_data,
               experiment_6_distance_varying_and_fifo,
               experiment_7_coarse_grained_window,
+              multi_window,
+              bounded_disorder,
 
 );
 
@@ -130,7 +249,7 @@ criterion_main!(benches);
 fn experiment_1_varying_distance(criterion: &mut Criterion) {
     // Setup
     let mut tree = FIBA::<Time, Value, BinOp>::new();
-    let timestamps = (2 as i64).pow(22);
+    let timestamps = 2_i64.pow(22);
     for t in 0..timestamps {
         tree.insert(Time(t), Value(0));
     }
@@ -138,7 +257,7 @@ fn experiment_1_varying_distance(criterion: &mut Criterion) {
     let g = g.throughput(Throughput::Elements(1));
     // Experiment
     for exponent in 0..22 {
-        let d = Time((2 as i64).pow(exponent));
+        let d = Time(2_i64.pow(exponent));
         g.bench_with_input(format!("2^{}", exponent), &d, |bench, d| {
             bench.iter(|| {
                 tree.insert(black_box(*d), black_box(Value(0)));
@@ -190,7 +309,7 @@ fn experiment_1_varying_distance(criterion: &mut Criterion) {
 fn experiment_2_latency(criterion: &mut Criterion) {
     // Setup
     let mut tree = FIBA::<Time, Value, BinOp>::new();
-    let timestamps = (2 as i64).pow(22);
+    let timestamps = 2_i64.pow(22);
     for t in 0..timestamps {
         tree.insert(Time(t), Value(0));
     }
@@ -198,7 +317,7 @@ fn experiment_2_latency(criterion: &mut Criterion) {
     let g = g.throughput(Throughput::Elements(1));
     // Experiment
     for &exponent in [0, 20].iter() {
-        let d = Time((2 as i64).pow(exponent));
+        let d = Time(2_i64.pow(exponent));
         g.bench_with_input(format!("2^{}", exponent), &d, |bench, d| {
             bench.iter(|| {
                 tree.insert(black_box(*d), black_box(Value(0)));
@@ -240,7 +359,67 @@ fn experiment_2_latency(criterion: &mut Criterion) {
 // * Compare against in-order aggregators
 // * Window size 2^0 .. 2^22
 // * two_stacks, daba, reactive
-fn experiment_3_fifo_in_order_data(criterion: &mut Criterion) {}
+// * two_stacks_lite, daba_deque, daba_lite, ioa (not part of the paper)
+fn experiment_3_fifo_in_order_data(criterion: &mut Criterion) {
+    let mut g = criterion.benchmark_group("fifo");
+    g.throughput(Throughput::Elements(1));
+    for exponent in (0..=22).step_by(2) {
+        let n = 2_i32.pow(exponent);
+        bench_fifo::<TwoStacks<Value, BinOp>, _, _>(&mut g, "sum/two_stacks", n, Value);
+        bench_fifo::<TwoStacksLite<Value, BinOp>, _, _>(&mut g, "sum/two_stacks_lite", n, Value);
+        bench_fifo::<DABA<Value, BinOp>, _, _>(&mut g, "sum/daba", n, Value);
+        bench_fifo::<DABA<Value, BinOp, Deque<Item<Value>>>, _, _>(
+            &mut g,
+            "sum/daba_deque",
+            n,
+            Value,
+        );
+        bench_fifo::<DABALite<Value, BinOp>, _, _>(&mut g, "sum/daba_lite", n, Value);
+        bench_fifo::<IOA<Value, BinOp>, _, _>(&mut g, "sum/ioa", n, Value);
+        bench_fifo::<RA<Value, BinOp>, _, _>(&mut g, "sum/reactive", n, Value);
+    }
+    // Each bloom filter takes 2KiB, so the windows are kept smaller
+    for exponent in (0..=14).step_by(2) {
+        let n = 2_i32.pow(exponent);
+        bench_fifo::<TwoStacks<Bloom, Union>, _, _>(&mut g, "bloom/two_stacks", n, Bloom::new);
+        bench_fifo::<TwoStacksLite<Bloom, Union>, _, _>(
+            &mut g,
+            "bloom/two_stacks_lite",
+            n,
+            Bloom::new,
+        );
+        bench_fifo::<DABA<Bloom, Union>, _, _>(&mut g, "bloom/daba", n, Bloom::new);
+        bench_fifo::<DABALite<Bloom, Union>, _, _>(&mut g, "bloom/daba_lite", n, Bloom::new);
+        bench_fifo::<IOA<Bloom, Union>, _, _>(&mut g, "bloom/ioa", n, Bloom::new);
+        bench_fifo::<RA<Bloom, Union>, _, _>(&mut g, "bloom/reactive", n, Bloom::new);
+    }
+}
+
+fn bench_fifo<Window, Val, Op>(
+    g: &mut BenchmarkGroup<WallTime>,
+    name: &str,
+    n: i32,
+    value: impl Fn(i32) -> Val,
+) where
+    Window: FifoWindow<Val, Op>,
+    Op: Operator,
+{
+    // Setup
+    let mut window = Window::new();
+    for i in 0..n {
+        window.push(value(i));
+    }
+    // Experiment
+    let mut i = n;
+    g.bench_with_input(BenchmarkId::new(name, n), &n, |bench, _| {
+        bench.iter(|| {
+            i += 1;
+            window.pop();
+            window.push(black_box(value(i)));
+            window.query();
+        })
+    });
+}
 
 // One of the benefits of FiBA is that it supports range queries while maintaining logarithmic
 // performance for queries over that range. Range queries enable window sharing: a single window
@@ -281,7 +460,7 @@ fn experiment_3_fifo_in_order_data(criterion: &mut Criterion) {}
 // * range: single tree
 // * measure throughput
 // * Out-of-order insert, in-order evict
-fn experiment_4_window_sharing(criterion: &mut Criterion) {
+fn experiment_4_window_sharing(_criterion: &mut Criterion) {
     //     let mut tree = Tree::new();
 }
 
@@ -307,7 +486,7 @@ fn experiment_4_window_sharing(criterion: &mut Criterion) {
 // The mean out-of-order distance is d = 56.47 (≈ 85.9 seconds). However, up to 99% of events have
 // d ≤ 9 (≈ 149 seconds). The severely out-of-order bursts show up in the last 0.01%, with d ≥ 150,
 // 000. The most severe has d ≈ 1 million (17.7 days late).
-fn experiment_5_real_data(criterion: &mut Criterion) {}
+fn experiment_5_real_data(_criterion: &mut Criterion) {}
 
 // How does FiBA perform relative to the state-of-the-art open- source counterparts? To answer this
 // question as well as to understand FiBA’s performance characteristics in a different environment,
@@ -333,7 +512,7 @@ fn experiment_5_real_data(criterion: &mut Criterion) {}
 // C++ experiments. The throughput of Flink’s built-in algorithm decreases linearly with the window
 // size and is never competitive. We stopped the Flink experiment at window size n = 213 = 8, 192,
 // after which point each run became too expensive.
-fn experiment_6_distance_varying_and_fifo(criterion: &mut Criterion) {
+fn experiment_6_distance_varying_and_fifo(_criterion: &mut Criterion) {
     //     let mut tree = Tree::new();
 }
 
@@ -358,6 +537,122 @@ fn experiment_6_distance_varying_and_fifo(criterion: &mut Criterion) {
 // granularity. This may seem counterintuitive as FiBA is already an O(1)-time algorithm; however,
 // because coarse- grained sliding combines items, insert creates a new entry less often and evict
 // occurs less frequently—hence, less total work overall.
-fn experiment_7_coarse_grained_window(criterion: &mut Criterion) {
+fn experiment_7_coarse_grained_window(_criterion: &mut Criterion) {
     //     let mut tree = Tree::new();
 }
+
+// Not part of the paper: a single stream is aggregated over multiple FIFO windows
+// at once, e.g., the last minute, five minutes and hour of a dashboard.
+//
+// Summary:
+// * Queries over the 2^4, 2^8, 2^12 and 2^16 most recent items
+// * Rounds of insert, then read all results
+// * sum: slide_side, slick_deque_inv, reactive
+// * max: slick_deque_non_inv, reactive
+fn multi_window(criterion: &mut Criterion) {
+    let ranges = [0..1 << 4, 0..1 << 8, 0..1 << 12, 0..1 << 16];
+    let mut g = criterion.benchmark_group("multi_window");
+    g.throughput(Throughput::Elements(1));
+    bench_multi_window::<SlideSide<Value, BinOp>, BinOp>(&mut g, "sum/slide_side", &ranges);
+    bench_multi_window::<SlickDequeInv<Value, BinOp>, BinOp>(
+        &mut g,
+        "sum/slick_deque_inv",
+        &ranges,
+    );
+    bench_multi_window::<MultiRA<Value, BinOp>, BinOp>(&mut g, "sum/reactive", &ranges);
+    bench_multi_window::<SlickDequeNonInv<Value, Max>, Max>(
+        &mut g,
+        "max/slick_deque_non_inv",
+        &ranges,
+    );
+    bench_multi_window::<MultiRA<Value, Max>, Max>(&mut g, "max/reactive", &ranges);
+}
+
+fn bench_multi_window<Window, Op>(
+    g: &mut BenchmarkGroup<WallTime>,
+    name: &str,
+    ranges: &[std::ops::Range<swag::Count>],
+) where
+    Window: MultiWindow<Value, Op>,
+    Op: Operator,
+{
+    // Setup
+    let mut window = Window::new(ranges);
+    for i in 0..1 << 16 {
+        window.insert(Value(i));
+    }
+    // Experiment
+    let mut i = 0;
+    g.bench_function(name, |bench| {
+        bench.iter(|| {
+            i += 1;
+            window.insert(black_box(Value(i)));
+            for (_, agg) in window.results() {
+                black_box(agg);
+            }
+        })
+    });
+}
+
+// Not part of the paper: a window of n = 2^16 time units slides over a stream where each item
+// arrives up to d time units after the newest item. The hybrid windows buffer items in a FiBA
+// until they are d time units old, and then move them into an in-order window.
+//
+// Summary:
+// * Window size n = 2^16
+// * Out-of-order distance uniformly distributed in 0..d, for d = 2^0 .. 2^16
+// * Rounds of insert, evict, and query
+// * bfinger2, hybrid_two_stacks, hybrid_daba
+fn bounded_disorder(criterion: &mut Criterion) {
+    let n = 2_i64.pow(16);
+    let mut g = criterion.benchmark_group("bounded_disorder");
+    g.throughput(Throughput::Elements(1));
+    for exponent in (0..=16).step_by(2) {
+        let d = 2_i64.pow(exponent);
+        bench_time_window(&mut g, "bfinger2", n, d, FIBA::<Time, Value, BinOp>::new());
+        bench_time_window(
+            &mut g,
+            "hybrid_two_stacks",
+            n,
+            d,
+            BoundedDisorder::<Time, Value, BinOp, TwoStacks<Value, BinOp>>::with_lateness(Time(d)),
+        );
+        bench_time_window(
+            &mut g,
+            "hybrid_daba",
+            n,
+            d,
+            BoundedDisorder::<Time, Value, BinOp, DABA<Value, BinOp>>::with_lateness(Time(d)),
+        );
+    }
+}
+
+fn bench_time_window<Window>(
+    g: &mut BenchmarkGroup<WallTime>,
+    name: &str,
+    n: i64,
+    d: i64,
+    mut window: Window,
+) where
+    Window: TimeWindow<Time, Value, BinOp>,
+{
+    // Setup
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut now = 0;
+    for _ in 0..n {
+        now += 1;
+        window
+            .insert(Time(now - rng.gen_range(0, d)), Value(0))
+            .unwrap();
+    }
+    // Experiment
+    g.bench_with_input(BenchmarkId::new(name, d), &d, |bench, d| {
+        bench.iter(|| {
+            now += 1;
+            let t = Time(now - rng.gen_range(0, *d));
+            window.insert(black_box(t), black_box(Value(0))).unwrap();
+            window.evict(black_box(Time(now - n)));
+            window.query();
+        })
+    });
+}
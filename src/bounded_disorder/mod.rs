@@ -0,0 +1,107 @@
+use crate::fiba::FIBA;
+use crate::time_fifo::TimeFifo;
+use crate::FifoWindow;
+use crate::OutOfOrder;
+use crate::TimeWindow;
+use alga::general::AbstractMonoid;
+use alga::general::Operator;
+use num_traits::Zero;
+use std::ops::Add;
+
+/// Time-based sliding window for streams whose values arrive at most
+/// `lateness` time units after the newest time seen so far. Recent values are
+/// buffered in a small FIBA, where they may arrive in any order. Once the
+/// watermark, i.e., the newest time minus the lateness, passes a value it is
+/// moved into an in-order FIFO window, such as DABA or Two-Stacks, which is
+/// cheaper to maintain. Values older than the watermark are only accepted if
+/// they are not older than the newest value of the FIFO window.
+pub struct BoundedDisorder<Time, Value, BinOp, Window>
+where
+    Time: Ord + Copy + Add<Output = Time> + Zero + 'static,
+    Value: Clone + AbstractMonoid<BinOp> + 'static,
+    BinOp: Operator + 'static,
+    Window: FifoWindow<Value, BinOp>,
+{
+    // Values at times after the watermark
+    buffer: FIBA<Time, Value, BinOp>,
+    // Values at times up to the watermark, all older than those of the buffer
+    window: TimeFifo<Time, Value, BinOp, Window>,
+    lateness: Time,
+    newest: Option<Time>,
+}
+
+impl<Time, Value, BinOp, Window> TimeWindow<Time, Value, BinOp>
+    for BoundedDisorder<Time, Value, BinOp, Window>
+where
+    Time: Ord + Copy + Add<Output = Time> + Zero + 'static,
+    Value: Clone + AbstractMonoid<BinOp> + 'static,
+    BinOp: Operator + 'static,
+    Window: FifoWindow<Value, BinOp>,
+{
+    /// Returns a window which only accepts values in time order
+    fn new() -> Self {
+        Self::with_lateness(Time::zero())
+    }
+    fn insert(&mut self, t: Time, v: Value) -> Result<(), OutOfOrder<Time, Value>> {
+        match self.newest {
+            Some(newest) if t + self.lateness <= newest => self.window.insert(t, v)?,
+            _ => self.buffer.insert(t, v),
+        }
+        if self.newest < Some(t) {
+            self.newest = Some(t);
+            self.flush();
+        }
+        Ok(())
+    }
+    fn evict(&mut self, t: Time) {
+        self.window.evict(t);
+        TimeWindow::evict(&mut self.buffer, t);
+    }
+    // The FIFO window holds the older values, so it goes first.
+    fn query(&self) -> Value {
+        self.window.query().operate(&self.buffer.query())
+    }
+    fn oldest(&self) -> Option<Time> {
+        // The values of the FIFO window are older than those of the buffer
+        self.window
+            .oldest()
+            .or_else(|| self.buffer.oldest().map(|(t, _)| t))
+    }
+}
+
+impl<Time, Value, BinOp, Window> BoundedDisorder<Time, Value, BinOp, Window>
+where
+    Time: Ord + Copy + Add<Output = Time> + Zero + 'static,
+    Value: Clone + AbstractMonoid<BinOp> + 'static,
+    BinOp: Operator + 'static,
+    Window: FifoWindow<Value, BinOp>,
+{
+    /// Returns a window which accepts any value less than `lateness` time
+    /// units older than the newest value
+    pub fn with_lateness(lateness: Time) -> Self {
+        Self {
+            buffer: FIBA::new(),
+            window: TimeFifo::new(),
+            lateness,
+            newest: None,
+        }
+    }
+    // Moves the buffered values which the watermark has passed, oldest first,
+    // into the FIFO window.
+    fn flush(&mut self) {
+        let newest = match self.newest {
+            Some(newest) => newest,
+            None => return,
+        };
+        while let Some((t, v)) = self.buffer.oldest() {
+            if newest < t + self.lateness {
+                break;
+            }
+            let v = v.clone();
+            self.buffer.evict(t);
+            // The buffer only holds values newer than those of the window
+            let result = self.window.insert(t, v);
+            debug_assert!(result.is_ok());
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use crate::FifoWindow;
+use alga::general::Operator;
+use std::marker::PhantomData;
+
+/// Count-based sliding window over the `size` most recent values, on top of
+/// any FIFO window algorithm.
+#[derive(Debug)]
+pub struct CountWindow<Value, BinOp, Window>
+where
+    BinOp: Operator,
+    Window: FifoWindow<Value, BinOp>,
+{
+    window: Window,
+    size: usize,
+    op: PhantomData<(Value, BinOp)>,
+}
+
+impl<Value, BinOp, Window> CountWindow<Value, BinOp, Window>
+where
+    BinOp: Operator,
+    Window: FifoWindow<Value, BinOp>,
+{
+    /// Returns a window over the `size` most recent values
+    pub fn new(size: usize) -> Self {
+        assert_ne!(size, 0, "Size of window must be greater than 0");
+        Self {
+            window: Window::with_capacity(size),
+            size,
+            op: PhantomData,
+        }
+    }
+    /// Pushes a value, evicts the oldest value if the window is full, and
+    /// returns the aggregate of the window.
+    pub fn push(&mut self, v: Value) -> Value {
+        if self.window.len() == self.size {
+            self.window.pop();
+        }
+        self.window.push(v);
+        self.window.query()
+    }
+    pub fn query(&self) -> Value {
+        self.window.query()
+    }
+    pub fn len(&self) -> usize {
+        self.window.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.window.is_empty()
+    }
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
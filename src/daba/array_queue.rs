@@ -0,0 +1,118 @@
+use super::queue::Queue;
+use arrayvec::Array;
+use std::fmt;
+use std::mem::MaybeUninit;
+
+/// A fixed-capacity ring buffer, which is stored inline in an array of type
+/// `A` and never allocates. Its pointers are absolute positions in the stream
+/// of pushed elements. Popped elements are moved out of their slots.
+pub struct ArrayQueue<A: Array> {
+    // Slots of the elements at positions popped..popped + len are initialized
+    slots: MaybeUninit<A>,
+    // Maximum number of elements, at most the capacity of `A`
+    capacity: usize,
+    // Number of elements which have been popped
+    popped: usize,
+    len: usize,
+}
+
+impl<A: Array> ArrayQueue<A> {
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+    fn index(&self, p: usize) -> usize {
+        p % self.capacity
+    }
+    // `A` has the representation of an array of `A::Item`
+    fn slot(&self, p: usize) -> *const A::Item {
+        unsafe { (self.slots.as_ptr() as *const A::Item).add(self.index(p)) }
+    }
+    fn slot_mut(&mut self, p: usize) -> *mut A::Item {
+        let i = self.index(p);
+        unsafe { (self.slots.as_mut_ptr() as *mut A::Item).add(i) }
+    }
+}
+
+impl<A: Array> Queue<A::Item> for ArrayQueue<A> {
+    type Pointer = usize;
+    fn new() -> Self {
+        Self::with_capacity(A::CAPACITY)
+    }
+    fn with_capacity(capacity: usize) -> Self {
+        assert!(
+            0 < capacity && capacity <= A::CAPACITY,
+            "capacity must be within 1..={}",
+            A::CAPACITY
+        );
+        Self {
+            slots: MaybeUninit::uninit(),
+            capacity,
+            popped: 0,
+            len: 0,
+        }
+    }
+    fn len(&self) -> usize {
+        self.len
+    }
+    fn is_full(&self) -> bool {
+        self.len == self.capacity
+    }
+    fn push_back(&mut self, v: A::Item) {
+        assert!(!self.is_full(), "push to a full queue");
+        let slot = self.slot_mut(self.popped + self.len);
+        unsafe { slot.write(v) };
+        self.len += 1;
+    }
+    fn pop_front(&mut self) -> Option<A::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let v = unsafe { self.slot(self.popped).read() };
+        self.popped += 1;
+        self.len -= 1;
+        Some(v)
+    }
+    fn begin(&self) -> usize {
+        self.popped
+    }
+    fn end(&self) -> usize {
+        self.popped + self.len
+    }
+    unsafe fn next(&self, p: usize) -> usize {
+        p + 1
+    }
+    unsafe fn prev(&self, p: usize) -> usize {
+        p - 1
+    }
+    unsafe fn read(&self, p: usize) -> &A::Item {
+        assert!(
+            self.popped <= p && p < self.popped + self.len,
+            "read of popped value"
+        );
+        &*self.slot(p)
+    }
+    unsafe fn write(&mut self, p: usize) -> &mut A::Item {
+        assert!(
+            self.popped <= p && p < self.popped + self.len,
+            "write of popped value"
+        );
+        &mut *self.slot_mut(p)
+    }
+}
+
+impl<A: Array> Drop for ArrayQueue<A> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+impl<A> fmt::Debug for ArrayQueue<A>
+where
+    A: Array,
+    A::Item: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let values = (self.popped..self.popped + self.len).map(|p| unsafe { &*self.slot(p) });
+        f.debug_list().entries(values).finish()
+    }
+}
@@ -1,77 +1,241 @@
+use super::queue::Queue;
 use arrayvec::ArrayVec;
+use std::fmt;
 use std::ptr::NonNull;
 
 type Arity = usize;
 
-const ARITY: Arity = 4;
+const ARITY: Arity = 64;
 
-struct Pointer<Value> {
+/// A pointer to a slot of a chunk
+pub struct Pointer<Value> {
     chunk: NonNull<Chunk<Value>>,
     offset: usize,
 }
 
 enum Elem<Value> {
+    // A slot whose value has been popped
     Sentinel,
     Value(Value),
 }
 
-struct ChunkedArrayQueue<Value> {
+/// A queue stored as a doubly linked list of fixed-size chunks. Unlike a
+/// `VecDeque`, it never moves its elements, so pushes are worst-case O(1) and
+/// pointers to elements stay valid. The chunk of the back always has a free
+/// slot, which is where the `end` pointer refers to. Emptied chunks are kept
+/// for reuse instead of being deallocated.
+pub struct ChunkedArrayQueue<Value> {
     front: Pointer<Value>,
     back: Pointer<Value>,
+    len: usize,
+    // Singly linked list of empty chunks
+    spare: Option<NonNull<Chunk<Value>>>,
 }
 
 struct Chunk<Value> {
-    values: ArrayVec<[Value; ARITY]>,
-    next: Pointer<Value>,
+    values: ArrayVec<[Elem<Value>; ARITY]>,
+    prev: Option<NonNull<Chunk<Value>>>,
+    next: Option<NonNull<Chunk<Value>>>,
 }
 
+// The queue owns its chunks like a Box does
+unsafe impl<Value: Send> Send for ChunkedArrayQueue<Value> {}
+unsafe impl<Value: Sync> Sync for ChunkedArrayQueue<Value> {}
+
 impl<Value> Pointer<Value> {
-    fn new(chunk: &Chunk<Value>) -> Self {
-        Self {
-            chunk: NonNull::from(chunk),
-            offset: 0,
-        }
+    fn new(chunk: NonNull<Chunk<Value>>) -> Self {
+        Self { chunk, offset: 0 }
+    }
+}
+
+impl<Value> Clone for Pointer<Value> {
+    fn clone(&self) -> Self {
+        *self
     }
-    fn set(self, other: Pointer<Value>) -> Pointer<Value> {
-        todo!()
+}
+
+impl<Value> Copy for Pointer<Value> {}
+
+impl<Value> PartialEq for Pointer<Value> {
+    fn eq(&self, other: &Self) -> bool {
+        self.chunk == other.chunk && self.offset == other.offset
     }
-    fn cmp(self, other: Pointer<Value>) -> Pointer<Value> {
-        todo!()
+}
+
+impl<Value> Eq for Pointer<Value> {}
+
+impl<Value> fmt::Debug for Pointer<Value> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pointer")
+            .field("chunk", &self.chunk)
+            .field("offset", &self.offset)
+            .finish()
     }
 }
 
 impl<Value> Chunk<Value> {
-    fn new(next: NonNull<Chunk<Value>>) -> Self {
-        todo!()
-        //         Self {
-        //             values: ArrayVec::new(),
-        //             next,
-        //         }
+    fn new(prev: Option<NonNull<Chunk<Value>>>) -> Self {
+        Self {
+            values: ArrayVec::new(),
+            prev,
+            next: None,
+        }
     }
 }
 
 impl<Value> ChunkedArrayQueue<Value> {
+    fn chunk(&self, p: Pointer<Value>) -> &Chunk<Value> {
+        unsafe { p.chunk.as_ref() }
+    }
+    fn chunk_mut(&mut self, p: Pointer<Value>) -> &mut Chunk<Value> {
+        unsafe { &mut *p.chunk.as_ptr() }
+    }
+    fn alloc(&mut self, prev: Option<NonNull<Chunk<Value>>>) -> NonNull<Chunk<Value>> {
+        match self.spare {
+            Some(mut chunk) => unsafe {
+                self.spare = chunk.as_ref().next;
+                chunk.as_mut().prev = prev;
+                chunk.as_mut().next = None;
+                chunk
+            },
+            None => Self::new_chunk(prev),
+        }
+    }
+    fn new_chunk(prev: Option<NonNull<Chunk<Value>>>) -> NonNull<Chunk<Value>> {
+        let chunk = Box::new(Chunk::new(prev));
+        unsafe { NonNull::new_unchecked(Box::into_raw(chunk)) }
+    }
+    fn free(&mut self, mut chunk: NonNull<Chunk<Value>>) {
+        unsafe {
+            chunk.as_mut().values.clear();
+            chunk.as_mut().next = self.spare;
+        }
+        self.spare = Some(chunk);
+    }
+    /// Returns an iterator over the values from the oldest to the newest
+    pub fn iter(&self) -> impl Iterator<Item = &Value> {
+        let mut p = self.front;
+        (0..self.len).map(move |_| unsafe {
+            // Only the pointers of the first `len` elements are visited
+            let v = self.read(p);
+            p = self.next(p);
+            v
+        })
+    }
+}
+
+impl<Value> Queue<Value> for ChunkedArrayQueue<Value> {
+    type Pointer = Pointer<Value>;
     fn new() -> Self {
-        //         let chunk = Box::new(Chunk::new());
-        //         let front = Pointer::new(&chunk);
-        //         let back = Pointer::new(&chunk);
-        //         Self { front, back }
-        todo!()
+        let chunk = Self::new_chunk(None);
+        Self {
+            front: Pointer::new(chunk),
+            back: Pointer::new(chunk),
+            len: 0,
+            spare: None,
+        }
+    }
+    fn with_capacity(capacity: usize) -> Self {
+        let mut queue = Self::new();
+        for _ in 0..capacity / ARITY {
+            let chunk = Self::new_chunk(None);
+            queue.free(chunk);
+        }
+        queue
+    }
+    fn len(&self) -> usize {
+        self.len
+    }
+    fn push_back(&mut self, v: Value) {
+        let back = self.back;
+        self.chunk_mut(back).values.push(Elem::Value(v));
+        self.back.offset += 1;
+        self.len += 1;
+        if self.back.offset == ARITY {
+            let chunk = self.alloc(Some(back.chunk));
+            self.chunk_mut(back).next = Some(chunk);
+            self.back = Pointer::new(chunk);
+        }
     }
-    fn push_back(&mut self, v: Value) {}
-    fn pop_front(&mut self) -> Value {
-        todo!()
+    fn pop_front(&mut self) -> Option<Value> {
+        if self.len == 0 {
+            return None;
+        }
+        let front = self.front;
+        let elem = std::mem::replace(
+            &mut self.chunk_mut(front).values[front.offset],
+            Elem::Sentinel,
+        );
+        self.front.offset += 1;
+        self.len -= 1;
+        if self.front.offset == ARITY {
+            let next = self.chunk(front).next.unwrap();
+            self.free(front.chunk);
+            self.front = Pointer::new(next);
+            self.chunk_mut(self.front).prev = None;
+        }
+        match elem {
+            Elem::Value(v) => Some(v),
+            Elem::Sentinel => unreachable!(),
+        }
     }
-    fn next(&self, p: Pointer<Value>) {
-        todo!()
+    fn begin(&self) -> Pointer<Value> {
+        self.front
     }
-    fn prev(&self, p: Pointer<Value>) {
-        todo!()
+    fn end(&self) -> Pointer<Value> {
+        self.back
     }
-    fn read(&self, p: Pointer<Value>) -> Value {
-        todo!()
+    unsafe fn next(&self, p: Pointer<Value>) -> Pointer<Value> {
+        if p.offset + 1 == ARITY {
+            Pointer::new(self.chunk(p).next.expect("next of end pointer"))
+        } else {
+            Pointer {
+                chunk: p.chunk,
+                offset: p.offset + 1,
+            }
+        }
     }
-    fn write(&self, p: Pointer<Value>) -> Value {
-        todo!()
+    unsafe fn prev(&self, p: Pointer<Value>) -> Pointer<Value> {
+        if p.offset == 0 {
+            Pointer {
+                chunk: self.chunk(p).prev.expect("prev of begin pointer"),
+                offset: ARITY - 1,
+            }
+        } else {
+            Pointer {
+                chunk: p.chunk,
+                offset: p.offset - 1,
+            }
+        }
+    }
+    unsafe fn read(&self, p: Pointer<Value>) -> &Value {
+        match &self.chunk(p).values[p.offset] {
+            Elem::Value(v) => v,
+            Elem::Sentinel => panic!("read of popped value"),
+        }
+    }
+    unsafe fn write(&mut self, p: Pointer<Value>) -> &mut Value {
+        match &mut self.chunk_mut(p).values[p.offset] {
+            Elem::Value(v) => v,
+            Elem::Sentinel => panic!("write of popped value"),
+        }
+    }
+}
+
+impl<Value> Drop for ChunkedArrayQueue<Value> {
+    fn drop(&mut self) {
+        for list in [Some(self.front.chunk), self.spare] {
+            let mut chunk = list;
+            while let Some(ptr) = chunk {
+                let boxed = unsafe { Box::from_raw(ptr.as_ptr()) };
+                chunk = boxed.next;
+            }
+        }
+    }
+}
+
+impl<Value: fmt::Debug> fmt::Debug for ChunkedArrayQueue<Value> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
     }
 }
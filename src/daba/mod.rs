@@ -1,85 +1,122 @@
-#![allow(unused)]
-mod chunked_array_queue;
+pub mod array_queue;
+pub mod chunked_array_queue;
+pub mod queue;
 
 use crate::FifoWindow;
 use alga::general::AbstractMonoid;
 use alga::general::Operator;
-use std::collections::VecDeque;
+use array_queue::ArrayQueue;
+use arrayvec::CapacityError;
+use chunked_array_queue::ChunkedArrayQueue;
+use queue::Queue;
 use std::fmt::Debug;
 use std::marker::PhantomData;
 
+/// Value of the window together with its partial aggregate
+#[derive(Debug, Clone)]
+pub struct Item<T> {
+    val: T,
+    agg: T,
+}
+
+/// DABA whose items are stored inline in an array of type `A`, e.g.,
+/// `ArrayDABA<Value, BinOp, [Item<Value>; 64]>`, without allocating.
+pub type ArrayDABA<T, O, A> = DABA<T, O, ArrayQueue<A>>;
+
 #[derive(Debug)]
-pub struct DABA<T, O>
+pub struct DABA<T, O, Q = ChunkedArrayQueue<Item<T>>>
 where
     T: Debug + AbstractMonoid<O> + Clone,
     O: Debug + Operator,
+    Q: Queue<Item<T>>,
 {
-    // ith oldest value in FIFO order stored at vi = vals[i]
-    vals: VecDeque<T>,
-    aggs: VecDeque<T>,
-    // 0 ≤ l ≤ r ≤ a ≤ b ≤ aggs.len()
-    l: usize, // Left,  ∀p ∈ l...r−1 : aggs[p] = vals[p] ⊕ ... ⊕ vals[r−1]
-    r: usize, // Right, ∀p ∈ r...a−1 : aggs[p] = vals[R] ⊕ ... ⊕ vals[p]
-    a: usize, // Accum, ∀p ∈ a...b−1 : aggs[p] = vals[p] ⊕ ... ⊕ vals[b−1]
-    b: usize, // Back,  ∀p ∈ b...e−1 : aggs[p] = vals[B] ⊕ ... ⊕ vals[p]
+    // ith oldest value in FIFO order stored at vi = items[i].val
+    items: Q,
+    // F ≤ l ≤ r ≤ a ≤ b ≤ E, where F = items.begin() and E = items.end()
+    // so l, r, a, and b are pointers which are safe to pass to items
+    l: Q::Pointer, // Left,  ∀p ∈ l...r−1 : aggs[p] = vals[p] ⊕ ... ⊕ vals[r−1]
+    r: Q::Pointer, // Right, ∀p ∈ r...a−1 : aggs[p] = vals[R] ⊕ ... ⊕ vals[p]
+    a: Q::Pointer, // Accum, ∀p ∈ a...b−1 : aggs[p] = vals[p] ⊕ ... ⊕ vals[b−1]
+    b: Q::Pointer, // Back,  ∀p ∈ b...e−1 : aggs[p] = vals[B] ⊕ ... ⊕ vals[p]
     op: PhantomData<O>,
 }
 
-impl<Value, BinOp> FifoWindow<Value, BinOp> for DABA<Value, BinOp>
+impl<Value, BinOp, Q> FifoWindow<Value, BinOp> for DABA<Value, BinOp, Q>
 where
     Value: Debug + AbstractMonoid<BinOp> + Clone,
     BinOp: Debug + Operator,
+    Q: Queue<Item<Value>>,
 {
-    fn new() -> DABA<Value, BinOp> {
-        DABA {
-            vals: VecDeque::new(),
-            aggs: VecDeque::new(),
-            l: 0,
-            r: 0,
-            a: 0,
-            b: 0,
-            op: PhantomData,
-        }
+    fn new() -> DABA<Value, BinOp, Q> {
+        Self::from_queue(Q::new())
     }
+    fn with_capacity(capacity: usize) -> DABA<Value, BinOp, Q> {
+        Self::from_queue(Q::with_capacity(capacity))
+    }
+    // Evicts the oldest value first if the queue is full
     fn push(&mut self, v: Value) {
-        self.aggs.push_back(self.agg_b().operate(&v));
-        self.vals.push_back(v);
+        if self.items.is_full() {
+            self.pop();
+        }
+        let agg = self.agg_b().operate(&v);
+        self.items.push_back(Item { val: v, agg });
         self.fixup();
     }
     fn pop(&mut self) {
-        if let Some(_) = self.vals.pop_front() {
-            self.aggs.pop_front();
-            self.l -= 1;
-            self.r -= 1;
-            self.a -= 1;
-            self.b -= 1;
+        if self.items.pop_front().is_some() {
             self.fixup();
         }
     }
     fn query(&self) -> Value {
         self.agg_f().operate(&self.agg_b())
     }
+    fn len(&self) -> usize {
+        self.items.len()
+    }
 }
 
-impl<T, O> DABA<T, O>
+impl<T, O, Q> DABA<T, O, Q>
 where
     T: Debug + AbstractMonoid<O> + Clone,
     O: Debug + Operator,
+    Q: Queue<Item<T>>,
 {
-    #[inline(always)]
-    fn agg_f(&self) -> T {
-        if self.aggs.is_empty() {
-            T::identity()
+    fn from_queue(items: Q) -> DABA<T, O, Q> {
+        let end = items.end();
+        DABA {
+            items,
+            l: end,
+            r: end,
+            a: end,
+            b: end,
+            op: PhantomData,
+        }
+    }
+    /// Returns true if the queue has a fixed capacity which is reached
+    pub fn is_full(&self) -> bool {
+        self.items.is_full()
+    }
+    /// Pushes a value, or returns it in an error if the queue is full
+    pub fn try_push(&mut self, v: T) -> Result<(), CapacityError<T>> {
+        if self.items.is_full() {
+            Err(CapacityError::new(v))
         } else {
-            self.aggs.front().unwrap().clone()
+            self.push(v);
+            Ok(())
         }
     }
     #[inline(always)]
+    fn agg_f(&self) -> T {
+        self.items
+            .front()
+            .map_or_else(T::identity, |item| item.agg.clone())
+    }
+    #[inline(always)]
     fn agg_b(&self) -> T {
-        if self.b == self.aggs.len() {
+        if self.b == self.items.end() {
             T::identity()
         } else {
-            self.aggs.back().unwrap().clone()
+            self.items.back().unwrap().agg.clone()
         }
     }
     #[inline(always)]
@@ -87,7 +124,7 @@ where
         if self.l == self.r {
             T::identity()
         } else {
-            self.aggs[self.l].clone()
+            unsafe { self.items.read(self.l).agg.clone() }
         }
     }
     #[inline(always)]
@@ -95,7 +132,7 @@ where
         if self.r == self.a {
             T::identity()
         } else {
-            self.aggs[self.a - 1].clone()
+            unsafe { self.items.read(self.items.prev(self.a)).agg.clone() }
         }
     }
     #[inline(always)]
@@ -103,11 +140,11 @@ where
         if self.a == self.b {
             T::identity()
         } else {
-            self.aggs[self.a].clone()
+            unsafe { self.items.read(self.a).agg.clone() }
         }
     }
     fn fixup(&mut self) {
-        if self.b == 0 {
+        if self.b == self.items.begin() {
             self.singleton()
         } else {
             if self.l == self.b {
@@ -127,7 +164,7 @@ where
     // without having to modify aggs.
     #[inline(always)]
     fn singleton(&mut self) {
-        self.l = self.aggs.len();
+        self.l = self.items.end();
         self.r = self.l;
         self.a = self.l;
         self.b = self.l;
@@ -138,8 +175,8 @@ where
     // turn lF and lB into lL and lR , which are aggregated in the same directions.
     #[inline(always)]
     fn flip(&mut self) {
-        self.l = 0;
-        self.a = self.aggs.len();
+        self.l = self.items.begin();
+        self.a = self.items.end();
         self.b = self.a;
     }
     // If L != B but L == R, that means that lL and lR are empty but lA is non-empty.
@@ -148,9 +185,11 @@ where
     // L, R, and A without having to modify aggs.
     #[inline(always)]
     fn shift(&mut self) {
-        self.a += 1;
-        self.r += 1;
-        self.l += 1;
+        unsafe {
+            self.a = self.items.next(self.a);
+            self.r = self.items.next(self.r);
+            self.l = self.items.next(self.l);
+        }
     }
     // If L != R, that means that lL is non-empty. As Section 4.4 will show, lL and lR
     // always have the same length, so lR is non-empty too. DABA shrinks both lL and lR
@@ -161,9 +200,13 @@ where
     // aggs entry for that element is vals[A−1] ⊕ ΣA⊕ .
     #[inline(always)]
     fn shrink(&mut self) {
-        self.aggs[self.l] = self.agg_l().operate(&self.agg_r()).operate(&self.agg_a());
-        self.l += 1;
-        self.aggs[self.a - 1] = self.vals[self.a - 1].operate(&self.agg_a());
-        self.a -= 1;
+        let agg = self.agg_l().operate(&self.agg_r()).operate(&self.agg_a());
+        unsafe {
+            self.items.write(self.l).agg = agg;
+            self.l = self.items.next(self.l);
+            let a = self.items.prev(self.a);
+            self.items.write(a).agg = self.items.read(a).val.operate(&self.agg_a());
+            self.a = a;
+        }
     }
 }
@@ -0,0 +1,134 @@
+use std::collections::VecDeque;
+use std::fmt::Debug;
+
+/// A FIFO queue whose elements can be accessed through pointers. A pointer to
+/// an element stays valid until that element is popped. The `end` pointer
+/// refers to the slot of the next element to be pushed, so it refers to that
+/// element once it has been pushed. The trait is sealed, since windows rely on
+/// its pointers being checked by the queues of this crate.
+pub trait Queue<T>: sealed::Sealed {
+    type Pointer: Copy + Eq + Debug;
+    fn new() -> Self;
+    /// Returns a queue which can hold `capacity` elements without allocating
+    fn with_capacity(capacity: usize) -> Self;
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Returns true if the queue has a fixed capacity which is reached
+    fn is_full(&self) -> bool {
+        false
+    }
+    fn push_back(&mut self, v: T);
+    fn pop_front(&mut self) -> Option<T>;
+    /// Returns the oldest element
+    fn front(&self) -> Option<&T> {
+        if self.is_empty() {
+            None
+        } else {
+            unsafe { Some(self.read(self.begin())) }
+        }
+    }
+    /// Returns the newest element
+    fn back(&self) -> Option<&T> {
+        if self.is_empty() {
+            None
+        } else {
+            unsafe { Some(self.read(self.prev(self.end()))) }
+        }
+    }
+    /// Returns a pointer to the oldest element
+    fn begin(&self) -> Self::Pointer;
+    /// Returns a pointer past the newest element
+    fn end(&self) -> Self::Pointer;
+    /// Returns a pointer to the element after `p`
+    ///
+    /// # Safety
+    ///
+    /// `p` must be a pointer of this queue to an element which has not been
+    /// popped.
+    unsafe fn next(&self, p: Self::Pointer) -> Self::Pointer;
+    /// Returns a pointer to the element before `p`
+    ///
+    /// # Safety
+    ///
+    /// `p` must be a pointer of this queue to an element which has not been
+    /// popped, or its `end` pointer, and must not be its `begin` pointer.
+    unsafe fn prev(&self, p: Self::Pointer) -> Self::Pointer;
+    /// Returns the element of `p`
+    ///
+    /// # Safety
+    ///
+    /// `p` must be a pointer of this queue to an element which has not been
+    /// popped.
+    unsafe fn read(&self, p: Self::Pointer) -> &T;
+    /// Returns the element of `p`
+    ///
+    /// # Safety
+    ///
+    /// `p` must be a pointer of this queue to an element which has not been
+    /// popped.
+    unsafe fn write(&mut self, p: Self::Pointer) -> &mut T;
+}
+
+pub(crate) mod sealed {
+    pub trait Sealed {}
+    impl<T> Sealed for super::Deque<T> {}
+    impl<T> Sealed for crate::daba::chunked_array_queue::ChunkedArrayQueue<T> {}
+    impl<A: arrayvec::Array> Sealed for crate::daba::array_queue::ArrayQueue<A> {}
+}
+
+/// A queue on top of a `VecDeque`, whose pointers are absolute positions in
+/// the stream of pushed elements. Pushes are amortized O(1), since the
+/// `VecDeque` is occasionally reallocated.
+#[derive(Debug)]
+pub struct Deque<T> {
+    deque: VecDeque<T>,
+    // Number of elements which have been popped
+    popped: usize,
+}
+
+impl<T> Queue<T> for Deque<T> {
+    type Pointer = usize;
+    fn new() -> Self {
+        Self {
+            deque: VecDeque::new(),
+            popped: 0,
+        }
+    }
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            deque: VecDeque::with_capacity(capacity),
+            popped: 0,
+        }
+    }
+    fn len(&self) -> usize {
+        self.deque.len()
+    }
+    fn push_back(&mut self, v: T) {
+        self.deque.push_back(v);
+    }
+    fn pop_front(&mut self) -> Option<T> {
+        let v = self.deque.pop_front()?;
+        self.popped += 1;
+        Some(v)
+    }
+    fn begin(&self) -> usize {
+        self.popped
+    }
+    fn end(&self) -> usize {
+        self.popped + self.deque.len()
+    }
+    unsafe fn next(&self, p: usize) -> usize {
+        p + 1
+    }
+    unsafe fn prev(&self, p: usize) -> usize {
+        p - 1
+    }
+    unsafe fn read(&self, p: usize) -> &T {
+        &self.deque[p - self.popped]
+    }
+    unsafe fn write(&mut self, p: usize) -> &mut T {
+        &mut self.deque[p - self.popped]
+    }
+}
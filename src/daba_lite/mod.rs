@@ -0,0 +1,153 @@
+use crate::FifoWindow;
+use alga::general::AbstractMonoid;
+use alga::general::Operator;
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+/// DABA Lite (Tangwongsan et al., VLDBJ'21). Like DABA, but the back and the
+/// right half of the front are stored as plain values instead of aggregates, so
+/// that a single deque suffices. The aggregates of those parts are instead kept
+/// in `agg_ra` and `agg_b`.
+#[derive(Debug)]
+pub struct DABALite<T, O>
+where
+    T: Debug + AbstractMonoid<O> + Clone,
+    O: Debug + Operator,
+{
+    // ith oldest value in FIFO order is vi
+    deque: VecDeque<T>,
+    // 0 ≤ l ≤ r ≤ a ≤ b ≤ deque.len()
+    l: usize,  // Left,  ∀p ∈ l...r−1 : deque[p] = vp ⊕ ... ⊕ v(r−1)
+    r: usize,  // Right, ∀p ∈ r...a−1 : deque[p] = vp
+    a: usize,  // Accum, ∀p ∈ a...b−1 : deque[p] = vp ⊕ ... ⊕ v(b−1)
+    b: usize,  // Back,  ∀p ∈ b...e−1 : deque[p] = vp
+    agg_ra: T, // vr ⊕ ... ⊕ v(b−1)
+    agg_b: T,  // vb ⊕ ... ⊕ v(e−1)
+    op: PhantomData<O>,
+}
+
+impl<Value, BinOp> FifoWindow<Value, BinOp> for DABALite<Value, BinOp>
+where
+    Value: Debug + AbstractMonoid<BinOp> + Clone,
+    BinOp: Debug + Operator,
+{
+    fn new() -> DABALite<Value, BinOp> {
+        DABALite {
+            deque: VecDeque::new(),
+            l: 0,
+            r: 0,
+            a: 0,
+            b: 0,
+            agg_ra: Value::identity(),
+            agg_b: Value::identity(),
+            op: PhantomData,
+        }
+    }
+    fn with_capacity(capacity: usize) -> DABALite<Value, BinOp> {
+        DABALite {
+            deque: VecDeque::with_capacity(capacity),
+            l: 0,
+            r: 0,
+            a: 0,
+            b: 0,
+            agg_ra: Value::identity(),
+            agg_b: Value::identity(),
+            op: PhantomData,
+        }
+    }
+    fn push(&mut self, v: Value) {
+        self.agg_b = self.agg_b.operate(&v);
+        self.deque.push_back(v);
+        self.fixup();
+    }
+    fn pop(&mut self) {
+        if self.deque.pop_front().is_some() {
+            self.l -= 1;
+            self.r -= 1;
+            self.a -= 1;
+            self.b -= 1;
+            self.fixup();
+        }
+    }
+    fn query(&self) -> Value {
+        self.agg_f().operate(&self.agg_b)
+    }
+    fn len(&self) -> usize {
+        self.deque.len()
+    }
+}
+
+impl<T, O> DABALite<T, O>
+where
+    T: Debug + AbstractMonoid<O> + Clone,
+    O: Debug + Operator,
+{
+    #[inline(always)]
+    fn agg_f(&self) -> T {
+        if self.b == 0 {
+            T::identity()
+        } else {
+            self.deque.front().unwrap().clone()
+        }
+    }
+    #[inline(always)]
+    fn agg_a(&self) -> T {
+        if self.a == self.b {
+            T::identity()
+        } else {
+            self.deque[self.a].clone()
+        }
+    }
+    fn fixup(&mut self) {
+        if self.b == 0 {
+            self.singleton()
+        } else {
+            if self.l == self.b {
+                self.flip()
+            }
+            if self.l == self.r {
+                self.shift()
+            } else {
+                self.shrink()
+            }
+        }
+    }
+    // The front is empty, so the back holds at most one value, which is also its
+    // own aggregate.
+    #[inline(always)]
+    fn singleton(&mut self) {
+        self.l = self.deque.len();
+        self.r = self.l;
+        self.a = self.l;
+        self.b = self.l;
+        self.agg_b = T::identity();
+    }
+    // The front is aggregated to its end, and the back holds plain values, so
+    // they can be turned into lL and lR by moving pointers around.
+    #[inline(always)]
+    fn flip(&mut self) {
+        self.l = 0;
+        self.a = self.deque.len();
+        self.b = self.a;
+        self.agg_ra = std::mem::replace(&mut self.agg_b, T::identity());
+    }
+    // lL and lR are empty, so the first value of lA is already aggregated to the
+    // end of the front.
+    #[inline(always)]
+    fn shift(&mut self) {
+        self.a += 1;
+        self.r += 1;
+        self.l += 1;
+    }
+    // Shrinks lL and lR by one value each. The first value of lL is completed
+    // with ΣR⊕ΣA, and the last value of lR is aggregated onto lA. The union of
+    // lR and lA stays the same, so `agg_ra` is left as is.
+    #[inline(always)]
+    fn shrink(&mut self) {
+        self.deque[self.l] = self.deque[self.l].operate(&self.agg_ra);
+        self.l += 1;
+        self.deque[self.a - 1] = self.deque[self.a - 1].operate(&self.agg_a());
+        self.a -= 1;
+    }
+}
@@ -1,6 +1,8 @@
 // All credits for comments below goes to:
 //   Tangwongsan, K., Hirzel, M. and Schneider, S., 2019. Optimal and general out-of-order sliding-window aggregation. Proceedings of the VLDB Endowment, 12(10), pp.1167-1180.
-mod pretty;
+use crate::OutOfOrder;
+use crate::TimeWindow;
+use crate::TimeWindowExtra;
 use crate::Uid;
 use alga::general::AbstractMonoid;
 use alga::general::Operator;
@@ -134,17 +136,17 @@ where
     // Checks
     #[inline(always)]
     fn is_leaf(&self) -> bool {
-        self.children.len() == 0
+        self.children.is_empty()
     }
     #[inline(always)]
     fn is_root(&self) -> bool {
         self.parent.is_none()
     }
-    fn is_descendent_of(&self, other: &Node<Time, Value, BinOp>) -> bool {
+    fn is_descendent_of(&self, uid: Uid) -> bool {
         let mut node = self;
         while node.parent.is_some() {
             node = node.get_parent();
-            if node.uid == other.uid {
+            if node.uid == uid {
                 return true;
             }
         }
@@ -173,7 +175,7 @@ where
     }
     fn get_oldest(&mut self) -> (&mut Node<Time, Value, BinOp>, Item<Time, Value>) {
         let mut node = self;
-        while node.children.first().is_some() {
+        while !node.children.is_empty() {
             node = node.children.first_mut().unwrap();
         }
         let item = node.items.first().unwrap().clone();
@@ -183,7 +185,7 @@ where
     // BTree operations: Splits a node in two by the median item/child. The median item
     // separating the splitted node is inserted at the parent node.
     fn split(&mut self, tree: &mut FIBA<Time, Value, BinOp>) {
-        let mut left = self;
+        let left = self;
         // Create new node
         let mut right = tree.new_node();
         let parent = left.get_parent();
@@ -245,7 +247,7 @@ where
         };
         let right = self.children.remove(right_idx);
         let middle_item = self.items.remove(left_idx);
-        let mut left = &mut self.children[left_idx];
+        let left = &mut self.children[left_idx];
         let left_ptr = NonNull::from(left.as_ref());
         if right.spine.right && right.is_leaf() {
             tree.right_finger = left_ptr;
@@ -275,11 +277,11 @@ where
             // Steal from the right sibling
             let sibling_child = sibling.children.pop_at(0);
             let sibling_item = sibling.items.remove(0);
-            // Steal from the parent
-            let parent_item = parent.items.remove(0);
+            // Steal the item between the node and the sibling from the parent
+            let parent_item = parent.items.remove(node_idx);
             let node = &mut parent.children[node_idx];
             node.items.push(parent_item);
-            parent.items.insert(sibling_idx - 1, sibling_item);
+            parent.items.insert(node_idx, sibling_item);
             if let Some(mut sibling_child) = sibling_child {
                 sibling_child.parent = Some(NonNull::from(node.as_ref()));
                 node.children.push(sibling_child);
@@ -375,9 +377,8 @@ where
         };
     }
     fn local_repair_agg_if_up(&mut self) {
-        match self.agg_kind() {
-            AggKind::Up => self.agg = self.up_agg(),
-            _ => {}
+        if self.agg_kind() == AggKind::Up {
+            self.agg = self.up_agg();
         }
     }
     fn local_search(&self, t: Time) -> Option<usize> {
@@ -423,38 +424,30 @@ where
         }
         node
     }
+    // Walks up the left spine while t may be within the parent, and then
+    // down to the node where t belongs.
     fn search_from_left_finger(&mut self, t: Time) -> &mut Node<Time, Value, BinOp> {
         let mut node = self;
-        while let Err(i) = node.item_idx(t) {
-            if node.parent.is_none() {
-                break;
-            } else {
-                let parent = node.get_parent();
-                if parent.items.first().unwrap().time <= t {
-                    node = parent;
-                } else if !node.is_leaf() {
-                    return node.children[i].search(t);
-                } else {
-                    break;
+        while node.item_idx(t).is_err() {
+            match node.parent {
+                Some(_) if node.get_parent().items.first().unwrap().time <= t => {
+                    node = node.get_parent()
                 }
+                _ => return node.search(t),
             }
         }
         node
     }
+    // Walks up the right spine while t may be within the parent, and then
+    // down to the node where t belongs.
     fn search_from_right_finger(&mut self, t: Time) -> &mut Node<Time, Value, BinOp> {
         let mut node = self;
-        while let Err(i) = node.item_idx(t) {
-            if node.parent.is_none() {
-                break;
-            } else {
-                let parent = node.get_parent();
-                if t <= parent.items.first().unwrap().time {
-                    node = parent;
-                } else if !node.is_leaf() {
-                    return node.children[i].search(t);
-                } else {
-                    break;
+        while node.item_idx(t).is_err() {
+            match node.parent {
+                Some(_) if t <= node.get_parent().items.last().unwrap().time => {
+                    node = node.get_parent()
                 }
+                _ => return node.search(t),
             }
         }
         node
@@ -464,20 +457,20 @@ where
     // rebalancing, starting above the node where rebalancing topped out and
     // visiting all ancestors up to the root.
     fn repair_aggs(&mut self, hit: Spine) {
-        self.repair_up();
-        self.repair_left(hit.left);
-        self.repair_right(hit.right);
+        // The spines are repaired downwards from where repairing up stopped
+        let top = self.repair_up();
+        top.repair_left(hit.left);
+        top.repair_right(hit.right);
     }
-    fn repair_up(&mut self) {
+    fn repair_up(&mut self) -> &mut Node<Time, Value, BinOp> {
+        // Rebalancing may top out at a node whose aggregate is not repaired
         let mut node = self;
-        if node.has_agg_up() {
-            while node.has_agg_up() {
-                node = node.get_parent();
-                node.local_repair_agg();
-            }
-        } else {
+        node.local_repair_agg();
+        while node.has_agg_up() {
+            node = node.get_parent();
             node.local_repair_agg();
         }
+        node
     }
     fn repair_left(&mut self, hit_left: bool) {
         let mut node = self;
@@ -529,7 +522,7 @@ where
             hit.left = hit.left || node.spine.left;
             hit.right = hit.right || node.spine.right;
         }
-        return (node, hit);
+        (node, hit)
     }
     // Rebalance the tree, walking from that node towards the root as necessary
     // to fix any size invariant violations, while also repairing aggregate
@@ -556,7 +549,8 @@ where
                 if parent.is_root() && parent.get_arity() == 1 {
                     tree.height_decrease();
                 } else {
-                    // parent.local_repair_agg(); // ????
+                    // NOTE: parent is repaired later
+                    node.local_repair_agg();
                     node = parent;
                 }
             } else {
@@ -567,7 +561,7 @@ where
                 node.local_repair_agg_if_up();
             }
             hit.left = hit.left || node.spine.left;
-            hit.right = hit.right | node.spine.right;
+            hit.right = hit.right || node.spine.right;
         }
         (node, hit)
     }
@@ -591,9 +585,15 @@ where
         leaf.local_evict_time_and_value(item.time);
         // Writes substitute over the evicted slot
         node.items[idx] = item;
-        let (mut top, mut hit) = leaf.rebalance_for_evict(Some(node.uid), tree);
-        if top.is_descendent_of(node) {
-            while top.uid != node.uid {
+        // The node may be freed by a merge during rebalancing, so it is
+        // identified by its uid which is kept by merged nodes
+        let uid = node.uid;
+        let (mut top, mut hit) = leaf.rebalance_for_evict(Some(uid), tree);
+        if top.is_descendent_of(uid) {
+            // Rebalancing topped out below the node, so the aggregates are
+            // repaired up to the node
+            top.local_repair_agg_if_up();
+            while top.uid != uid {
                 top = top.get_parent();
                 hit.left = hit.left || top.spine.left;
                 hit.right = hit.right || top.spine.right;
@@ -658,6 +658,12 @@ where
             }
         }
     }
+    // Returns the oldest time in the window together with its value, i.e.,
+    // (t0,v0), which is the first item of the left-most leaf.
+    pub fn oldest(&self) -> Option<(Time, &Value)> {
+        let leaf = unsafe { self.left_finger.as_ref() };
+        leaf.items.first().map(|item| (item.time, &item.value))
+    }
     // Checks whether t is already in the window, i.e. whether there is an i
     // such that t = ti. If so, it replaces (ti,vi) by (ti,vi+v). Otherwise, it
     // inserts (t,v) into the window at the appropriate location.
@@ -687,6 +693,7 @@ where
     }
     // Checks whether t is in the window, i.e., whether there is an i such that
     // t = ti. If so, it removes (ti,vi) from the window. Otherwise it does nothing.
+    // Unlike `TimeWindow::evict`, values at other times are kept.
     pub fn evict(&mut self, t: Time) {
         let tree = unsafe { (self as *mut FIBA<Time, Value, BinOp>).as_mut().unwrap() };
         let node = self.search_node(t);
@@ -700,6 +707,16 @@ where
             top.repair_aggs(hit);
         }
     }
+    // Removes (ti,vi) such that t = ti from the window, and returns vi. If there
+    // is no such i, it does nothing.
+    pub fn remove(&mut self, t: Time) -> Option<Value> {
+        let node = self.search_node(t);
+        let value = node
+            .local_search(t)
+            .map(|idx| node.items[idx].value.clone())?;
+        self.evict(t);
+        Some(value)
+    }
     // Search for the node where t belongs. We keep fingers pointers to the
     // left- and right-most leaves. Also, we keep parent pointers at each node.
     // Hence, search can start at the nearest finger, walk up to the nearest
@@ -716,31 +733,37 @@ where
     // Aggregates exactly the values in the window whose times fall within the range.
     // If the subrange contains no values, it returns the identity.
     pub fn range_query(&mut self, range: Range<Time>) -> Value {
+        self.span_query(range.start, Bound::Closed(range.end))
+    }
+    fn span_query(&mut self, start: Time, end: Bound<Time>) -> Value {
         // uses recursion starting from the least-common ancestor node whose
         // subtree encompasses the queried range
-        let node_from = self.search_node(range.start);
-        let node_top = Self::least_common_ancestor(node_from, range.end);
+        let node_from = self.search_node(start);
+        let node_top = match end {
+            Bound::Closed(t) | Bound::Excluded(t) => Self::least_common_ancestor(node_from, t),
+            Bound::Open => self.root.as_mut(),
+        };
         // invoke at most two chains of recursive calls, one visiting ancestors
         // of node_from and the other visiting ancestors of node_to
-        let span = Span::new(Bound::Closed(range.start), Bound::Closed(range.end));
+        let span = Span::new(Bound::Closed(start), end);
         Self::query_rec(node_top, span)
     }
-    // Requires that node.time < time
+    // Requires that the subtree of the node encompasses the start of the range
     fn least_common_ancestor(
         mut node: &mut Node<Time, Value, BinOp>,
         time: Time,
     ) -> &mut Node<Time, Value, BinOp> {
-        loop {
-            if node.is_root() {
-                return node;
-            }
+        // Walks up until the subtree of the node also encompasses time, i.e.,
+        // until the item which follows the node in its parent is after time
+        while !node.is_root() {
             let parent = node.get_parent();
-            if parent.items.last().unwrap().time <= time {
-                node = parent;
-            } else {
-                return node;
+            let idx = parent.child_idx(node.uid);
+            match parent.items.get(idx) {
+                Some(item) if time < item.time => return node,
+                _ => node = parent,
             }
         }
+        node
     }
     fn query_rec(node: &mut Node<Time, Value, BinOp>, span: Span<Time>) -> Value {
         // The insight for preventing spurious recursive calls is that one
@@ -768,9 +791,9 @@ where
         }
         for (i, Item { time, value }) in node.items.iter().enumerate() {
             if span.start.leq(time) && span.end.geq(time) {
-                res = res.operate(&value);
+                res = res.operate(value);
             }
-            if !node.is_leaf() && i + 1 <= node.get_arity() - 2 {
+            if !node.is_leaf() && i < node.get_arity() - 2 {
                 let t_ii = node.items[i + 1].time;
                 if span.end.gt(time) && span.start.lt(&t_ii) {
                     let t_a = if span.start.leq(time) {
@@ -808,10 +831,89 @@ where
     }
 }
 
+impl<Time, Value, BinOp> Default for FIBA<Time, Value, BinOp>
+where
+    Time: Clone + Copy + Ord + 'static,
+    Value: Clone + AbstractMonoid<BinOp> + 'static,
+    BinOp: Operator + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Time, Value, BinOp> TimeWindowExtra<Time, Value, BinOp> for FIBA<Time, Value, BinOp>
+where
+    Time: Clone + Copy + Ord + 'static,
+    Value: Clone + AbstractMonoid<BinOp> + 'static,
+    BinOp: Operator + 'static,
+{
+    // Unlike the inherent range query, the end of the range is excluded.
+    fn range_query(&mut self, range: Range<Time>) -> Value {
+        self.span_query(range.start, Bound::Excluded(range.end))
+    }
+    // Walks down from the root towards t. The oldest item at or after t in a
+    // node is a candidate, and older candidates can only be in the child
+    // before it.
+    fn oldest_since(&self, t: Time) -> Option<Time> {
+        let mut node = self.root.as_ref();
+        let mut oldest = None;
+        loop {
+            let idx = node
+                .items
+                .iter()
+                .position(|item| t <= item.time)
+                .unwrap_or_else(|| node.items.len());
+            if let Some(item) = node.items.get(idx) {
+                oldest = Some(item.time);
+            }
+            if node.is_leaf() {
+                return oldest;
+            }
+            node = &node.children[idx];
+        }
+    }
+}
+
+impl<Time, Value, BinOp> TimeWindow<Time, Value, BinOp> for FIBA<Time, Value, BinOp>
+where
+    Time: Clone + Copy + Ord + 'static,
+    Value: Clone + AbstractMonoid<BinOp> + 'static,
+    BinOp: Operator + 'static,
+{
+    fn new() -> Self {
+        Self::new()
+    }
+    // Values may be inserted at any time, so this never fails.
+    fn insert(&mut self, t: Time, v: Value) -> Result<(), OutOfOrder<Time, Value>> {
+        self.insert(t, v);
+        Ok(())
+    }
+    // Repeatedly evicts the oldest value, which is O(1) amortized per value.
+    // Unlike the inherent `evict`, all values before t are removed.
+    fn evict(&mut self, t: Time) {
+        while let Some((oldest, _)) = self.oldest() {
+            if oldest < t {
+                self.evict(oldest);
+            } else {
+                break;
+            }
+        }
+    }
+    fn query(&self) -> Value {
+        self.query()
+    }
+    fn oldest(&self) -> Option<Time> {
+        self.oldest().map(|(t, _)| t)
+    }
+}
+
 #[derive(Clone, Copy)]
 enum Bound<Time: Ord> {
     Open,
     Closed(Time),
+    // Only used for the end of a span
+    Excluded(Time),
 }
 
 #[derive(Clone, Copy)]
@@ -831,6 +933,7 @@ impl<Time: Ord> Bound<Time> {
     fn gt(&self, other: &Time) -> bool {
         match self {
             Bound::Closed(t) => other < t,
+            Bound::Excluded(t) => other < t,
             Bound::Open => true,
         }
     }
@@ -838,6 +941,7 @@ impl<Time: Ord> Bound<Time> {
     fn lt(&self, other: &Time) -> bool {
         match self {
             Bound::Closed(t) => t < other,
+            Bound::Excluded(t) => t < other,
             Bound::Open => true,
         }
     }
@@ -845,6 +949,7 @@ impl<Time: Ord> Bound<Time> {
     fn geq(&self, other: &Time) -> bool {
         match self {
             Bound::Closed(t) => other <= t,
+            Bound::Excluded(t) => other < t,
             Bound::Open => true,
         }
     }
@@ -852,14 +957,11 @@ impl<Time: Ord> Bound<Time> {
     fn leq(&self, other: &Time) -> bool {
         match self {
             Bound::Closed(t) => t <= other,
+            Bound::Excluded(t) => t < other,
             Bound::Open => true,
         }
     }
     fn is_open(&self) -> bool {
-        if let Bound::Open = self {
-            true
-        } else {
-            false
-        }
+        matches!(self, Bound::Open)
     }
 }
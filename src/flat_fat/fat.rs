@@ -19,4 +19,6 @@ where
     fn aggregate(&self) -> Value;
     fn prefix(&self, i: usize) -> Value;
     fn suffix(&self, i: usize) -> Value;
+    /// Aggregates a contiguous range of leaves
+    fn range_query(&self, range: std::ops::Range<usize>) -> Value;
 }
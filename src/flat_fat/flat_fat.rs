@@ -1,6 +1,10 @@
 use crate::flat_fat::fat::FAT;
 use alga::general::AbstractMonoid;
 use alga::general::Operator;
+use std::collections::BinaryHeap;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 
 #[derive(Debug)]
 pub struct FlatFAT<Value, BinOp>
@@ -18,6 +22,9 @@ where
     pub(crate) tree: Vec<Value>,
     /// Number of leaves which can be stored in the tree
     pub(crate) capacity: usize,
+    /// Scratch queue of internal nodes awaiting recomputation, kept between
+    /// calls to `update` so that batched updates do not allocate
+    dirty: BinaryHeap<usize>,
     binop: std::marker::PhantomData<BinOp>,
 }
 
@@ -68,38 +75,46 @@ where
     /// Creates an empty-window from a list of values
     fn with_capacity(capacity: usize) -> Self {
         assert_ne!(capacity, 0, "Capacity of window must be greater than 0");
+        // Leaves are only stored in order from left to right when the tree is
+        // perfect, which non-commutative operators depend on
+        let capacity = capacity.next_power_of_two();
         Self {
             tree: vec![Value::identity(); 2 * capacity - 1],
             binop: std::marker::PhantomData,
+            dirty: BinaryHeap::new(),
             capacity,
         }
     }
     /// Adds a batch of values to the window
+    ///
+    /// Every internal node is a parent of nodes with higher indices, so by
+    /// recomputing dirty nodes in descending index order each node is visited
+    /// after both of its children. Duplicates, which arise when leaves share
+    /// ancestors, come out of the queue back-to-back and are skipped. This way,
+    /// each affected node is recomputed exactly once per batch.
     fn update(&mut self, batch: &[(usize, Value)]) {
+        let mut dirty = std::mem::take(&mut self.dirty);
         for (idx, val) in batch {
             let leaf = self.leaf(*idx);
             self.tree[leaf] = val.clone();
+            if leaf != self.root() {
+                dirty.push(self.parent(leaf));
+            }
         }
-        let mut parents: Vec<usize> = batch
-            .iter()
-            .map(|&(idx, _)| self.parent(self.leaf(idx)))
-            .collect();
-        let mut new_parents: Vec<usize> = Vec::new();
-        loop {
-            for parent in parents.drain(..) {
-                let left = self.left(parent);
-                let right = self.right(parent);
-                self.tree[parent] = self.tree[left].operate(&self.tree[right]);
-                if parent != self.root() {
-                    new_parents.push(self.parent(parent));
-                }
+        let mut last = None;
+        while let Some(parent) = dirty.pop() {
+            if last == Some(parent) {
+                continue;
             }
-            if new_parents.is_empty() {
-                break;
-            } else {
-                std::mem::swap(&mut parents, &mut new_parents);
+            last = Some(parent);
+            let left = self.left(parent);
+            let right = self.right(parent);
+            self.tree[parent] = self.tree[left].operate(&self.tree[right]);
+            if parent != self.root() {
+                dirty.push(self.parent(parent));
             }
         }
+        self.dirty = dirty;
     }
     fn update_ordered(&mut self, values: &[Value]) {
         for (idx, val) in values.iter().enumerate() {
@@ -108,7 +123,7 @@ where
         }
     }
     fn update_parents(&mut self) {
-        for parent in (0..self.capacity - 1).into_iter().rev() {
+        for parent in (0..self.capacity - 1).rev() {
             let left = self.left(parent);
             let right = self.right(parent);
             self.tree[parent] = self.tree[left].operate(&self.tree[right]);
@@ -128,7 +143,7 @@ where
             }
             node = parent;
         }
-        return agg;
+        agg
     }
     fn suffix(&self, i: usize) -> Value {
         let mut node = self.leaf(i);
@@ -140,6 +155,294 @@ where
             }
             node = parent;
         }
-        return agg;
+        agg
+    }
+    // `usize::is_multiple_of` requires a newer Rust than the crate supports
+    #[allow(clippy::manual_is_multiple_of)]
+    fn range_query(&self, range: std::ops::Range<usize>) -> Value {
+        // Walks up from both ends of the range, collecting the nodes which
+        // hang just inside of it. Nodes on the left are combined from the
+        // left and nodes on the right from the right to preserve the order.
+        // Right children (and the root) have even indices.
+        let mut left_agg = Value::identity();
+        let mut right_agg = Value::identity();
+        let mut left = self.leaf(range.start);
+        let mut right = self.leaf(range.end);
+        while left < right {
+            if left % 2 == 0 {
+                left_agg = left_agg.operate(&self.tree[left]);
+                left += 1;
+            }
+            if right % 2 == 0 {
+                right -= 1;
+                right_agg = self.tree[right].operate(&right_agg);
+            }
+            left = self.parent(left);
+            right = self.parent(right);
+        }
+        left_agg.operate(&right_agg)
+    }
+}
+
+/// Parallel construction and bulk updates. Nodes at the same depth of the
+/// tree only depend on nodes below them, so each level is aggregated in
+/// parallel, starting from the deepest one. Every node is computed as
+/// `left.operate(right)`, exactly like in the sequential version, which makes
+/// the result identical to it.
+#[cfg(feature = "rayon")]
+impl<Value, BinOp> FlatFAT<Value, BinOp>
+where
+    Value: AbstractMonoid<BinOp> + Clone + std::fmt::Debug + Send + Sync,
+    BinOp: Operator,
+{
+    /// Creates an aggregate binary tree from a list of values, in parallel
+    pub fn par_new(values: &[Value]) -> Self {
+        let mut new = Self::with_capacity(values.len());
+        let leaf = new.leaf(0);
+        new.tree[leaf..]
+            .par_iter_mut()
+            .zip(values.par_iter())
+            .for_each(|(leaf, val)| *leaf = val.clone());
+        new.par_update_parents();
+        new
+    }
+    /// Updates all parents, in parallel
+    pub fn par_update_parents(&mut self) {
+        let internal = self.capacity - 1;
+        if internal == 0 {
+            return;
+        }
+        // Index of the first node of the deepest level which has parents
+        let mut start = self.root();
+        while self.left(start) < internal {
+            start = self.left(start);
+        }
+        loop {
+            let end = internal.min(self.left(start));
+            let (nodes, children) = self.tree.split_at_mut(end);
+            nodes[start..]
+                .par_iter_mut()
+                .enumerate()
+                .for_each(|(i, node)| {
+                    // The left child of node `start + i` is at `2 * (start + i) + 1`
+                    let left = 2 * (start + i) + 1 - end;
+                    *node = children[left].operate(&children[left + 1]);
+                });
+            if start == self.root() {
+                break;
+            }
+            start = self.parent(start);
+        }
+    }
+    /// Updates a batch of leaves, recomputing their ancestors level by level
+    /// in parallel. As with `update`, each ancestor is recomputed only once.
+    pub fn par_update(&mut self, batch: &[(usize, Value)]) {
+        let mut dirty = std::mem::take(&mut self.dirty);
+        for (idx, val) in batch {
+            let leaf = self.leaf(*idx);
+            self.tree[leaf] = val.clone();
+            if leaf != self.root() {
+                dirty.push(self.parent(leaf));
+            }
+        }
+        let mut level = Vec::new();
+        while let Some(&deepest) = dirty.peek() {
+            // Levels span the indices 2^d-1 .. 2^(d+1)-1
+            let start = ((deepest + 2).next_power_of_two() >> 1) - 1;
+            while let Some(&node) = dirty.peek() {
+                if node < start {
+                    break;
+                }
+                dirty.pop();
+                if level.last() != Some(&node) {
+                    level.push(node);
+                }
+            }
+            let tree = &self.tree;
+            let aggs: Vec<Value> = level
+                .par_iter()
+                .map(|&node| tree[2 * node + 1].operate(&tree[2 * node + 2]))
+                .collect();
+            for (node, agg) in level.drain(..).zip(aggs) {
+                self.tree[node] = agg;
+                if node != self.root() {
+                    dirty.push(self.parent(node));
+                }
+            }
+        }
+        self.dirty = dirty;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alga::general::AbstractMagma;
+    use alga::general::AbstractSemigroup;
+    use alga::general::Identity;
+    use std::cell::Cell;
+    use std::collections::HashSet;
+
+    thread_local! {
+        static OPERATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    struct Value(i64);
+
+    #[derive(Copy, Clone, Debug)]
+    struct Sum;
+
+    impl Operator for Sum {
+        fn operator_token() -> Sum {
+            Sum
+        }
+    }
+
+    impl Identity<Sum> for Value {
+        fn identity() -> Value {
+            Value(0)
+        }
+    }
+
+    impl AbstractMagma<Sum> for Value {
+        fn operate(&self, other: &Self) -> Self {
+            OPERATIONS.with(|count| count.set(count.get() + 1));
+            Value(self.0 + other.0)
+        }
+    }
+
+    impl AbstractSemigroup<Sum> for Value {}
+    impl AbstractMonoid<Sum> for Value {}
+
+    fn operations() -> usize {
+        OPERATIONS.with(|count| count.replace(0))
+    }
+
+    /// Returns the number of distinct internal nodes above a batch of leaves
+    fn ancestors(fat: &FlatFAT<Value, Sum>, batch: &[(usize, Value)]) -> usize {
+        let mut nodes = HashSet::new();
+        for &(idx, _) in batch {
+            let mut node = fat.leaf(idx);
+            while node != fat.root() {
+                node = fat.parent(node);
+                nodes.insert(node);
+            }
+        }
+        nodes.len()
+    }
+
+    #[test]
+    fn update_recomputes_each_ancestor_once() {
+        for &capacity in [1, 2, 7, 16, 100].iter() {
+            let mut fat = FlatFAT::<Value, Sum>::with_capacity(capacity);
+            let mut leaves = vec![0; capacity];
+            for &(start, len) in [(0, capacity), (capacity / 3, capacity / 2), (1, 1)].iter() {
+                let batch = (start..(start + len).min(capacity))
+                    .rev()
+                    .map(|idx| (idx, Value((idx * 7 + len) as i64)))
+                    .collect::<Vec<_>>();
+                for &(idx, Value(v)) in &batch {
+                    leaves[idx] = v;
+                }
+                operations();
+                fat.update(&batch);
+                assert_eq!(operations(), ancestors(&fat, &batch));
+                assert_eq!(fat.aggregate(), Value(leaves.iter().sum()));
+            }
+        }
+    }
+
+    #[test]
+    fn update_adjacent_leaves_is_linear() {
+        let capacity = 1 << 10;
+        let mut fat = FlatFAT::<Value, Sum>::with_capacity(capacity);
+        let batch = (0..64).map(|idx| (idx, Value(1))).collect::<Vec<_>>();
+        operations();
+        fat.update(&batch);
+        // 63 nodes inside the subtree of the batch, plus 4 ancestors above it
+        assert_eq!(operations(), 63 + 4);
+        assert_eq!(fat.aggregate(), Value(64));
+    }
+
+    // Concatenation, which is not commutative
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    struct Word(String);
+
+    #[derive(Copy, Clone, Debug)]
+    struct Concat;
+
+    impl Operator for Concat {
+        fn operator_token() -> Concat {
+            Concat
+        }
+    }
+
+    impl Identity<Concat> for Word {
+        fn identity() -> Word {
+            Word(String::new())
+        }
+    }
+
+    impl AbstractMagma<Concat> for Word {
+        fn operate(&self, other: &Self) -> Self {
+            Word(format!("{}{}", self.0, other.0))
+        }
+    }
+
+    impl AbstractSemigroup<Concat> for Word {}
+    impl AbstractMonoid<Concat> for Word {}
+
+    fn words(range: std::ops::Range<usize>) -> Vec<Word> {
+        range.map(|i| Word(format!("{},", i))).collect()
+    }
+
+    // Aggregate of the words in a range
+    fn concat(range: std::ops::Range<usize>) -> Word {
+        Word(words(range).into_iter().map(|word| word.0).collect())
+    }
+
+    #[test]
+    fn leaves_are_aggregated_in_order() {
+        for capacity in 1..=17 {
+            let fat = FlatFAT::<Word, Concat>::new(&words(0..capacity));
+            assert_eq!(fat.aggregate(), concat(0..capacity));
+            for i in 0..capacity {
+                assert_eq!(fat.prefix(i), concat(0..i + 1));
+                assert_eq!(fat.suffix(i), concat(i..capacity));
+            }
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    mod parallel {
+        use super::*;
+
+        #[test]
+        fn par_new_is_identical_to_new() {
+            for &capacity in [1, 2, 3, 7, 8, 100, 1000].iter() {
+                let values = words(0..capacity);
+                let sequential = FlatFAT::<Word, Concat>::new(&values);
+                let parallel = FlatFAT::<Word, Concat>::par_new(&values);
+                assert_eq!(sequential.tree, parallel.tree);
+            }
+        }
+
+        #[test]
+        fn par_update_is_identical_to_update() {
+            for &capacity in [1, 2, 3, 7, 8, 100, 1000].iter() {
+                let mut sequential = FlatFAT::<Word, Concat>::with_capacity(capacity);
+                let mut parallel = FlatFAT::<Word, Concat>::with_capacity(capacity);
+                for &(start, len) in [(0, capacity), (capacity / 3, capacity / 2), (1, 1)].iter() {
+                    let batch = (start..(start + len).min(capacity))
+                        .rev()
+                        .map(|idx| (idx, Word(format!("{}:{},", len, idx))))
+                        .collect::<Vec<_>>();
+                    sequential.update(&batch);
+                    parallel.par_update(&batch);
+                    assert_eq!(sequential.tree, parallel.tree);
+                }
+            }
+        }
     }
 }
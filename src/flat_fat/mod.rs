@@ -1,5 +1,6 @@
-pub(crate) mod fat;
-pub(crate) mod flat_fat;
-pub(crate) mod item;
+pub mod fat;
+#[allow(clippy::module_inception)]
+pub mod flat_fat;
 pub mod ra;
 pub mod ra_fifo;
+pub mod ra_multi;
@@ -1,12 +1,13 @@
-use alga::general::AbstractMagma;
 use alga::general::AbstractMonoid;
-use alga::general::Identity;
 use alga::general::Operator;
 
 use crate::flat_fat::fat::FAT;
 use crate::flat_fat::flat_fat::FlatFAT;
 use crate::FifoWindow;
 
+/// Reactive Aggregator. The items are stored in the leaves of a FlatFAT, which
+/// is used as a ring buffer that grows when it is 3/4 full and shrinks when it
+/// is 1/4 full.
 #[derive(Debug)]
 pub struct RA<Value, BinOp>
 where
@@ -15,8 +16,12 @@ where
 {
     fat: FlatFAT<Value, BinOp>,
     size: usize,
+    // Leaf of the oldest item
     front: usize,
+    // Leaf after the newest item
     back: usize,
+    // The tree is never shrunk below its initial capacity
+    min_capacity: usize,
 }
 
 impl<Value, BinOp> RA<Value, BinOp>
@@ -24,28 +29,18 @@ where
     Value: AbstractMonoid<BinOp> + Clone + std::fmt::Debug,
     BinOp: Operator + std::fmt::Debug,
 {
-    pub fn with_capacity(capacity: usize) -> Self {
-        Self {
-            fat: FlatFAT::with_capacity(capacity),
-            size: 0,
-            front: 0,
-            back: 0,
-        }
-    }
     fn inverted(&self) -> bool {
-        return self.front > self.back;
+        self.front > self.back
     }
     fn resize(&mut self, capacity: usize) {
-        let leaves = self.fat.leaves(0..self.size);
+        let leaves = self.fat.leaves(0..self.fat.capacity);
+        let items = (0..self.size)
+            .map(|i| leaves[(self.front + i) % self.fat.capacity].clone())
+            .collect::<Vec<_>>();
         let mut fat = FlatFAT::with_capacity(capacity);
-        if self.inverted() {
-            fat.update_ordered(&leaves[self.front..]);
-            fat.update_ordered(&leaves[..self.back]);
-        } else {
-            fat.update_ordered(&leaves[self.front..self.back]);
-        }
+        fat.update_ordered(&items);
+        fat.update_parents();
         self.fat = fat;
-        self.fat.update_parents();
         self.front = 0;
         self.back = self.size;
     }
@@ -57,8 +52,14 @@ where
     BinOp: Operator + std::fmt::Debug,
 {
     fn new() -> Self {
+        Self::with_capacity(0)
+    }
+    fn with_capacity(capacity: usize) -> Self {
+        // Leaves are reserved for a window which is at most 3/4 full
+        let fat = FlatFAT::with_capacity(capacity + capacity / 3 + 1);
         Self {
-            fat: FlatFAT::with_capacity(0),
+            min_capacity: fat.capacity,
+            fat,
             size: 0,
             front: 0,
             back: 0,
@@ -67,21 +68,24 @@ where
     fn push(&mut self, v: Value) {
         self.fat.update(&[(self.back, v)]);
         self.size += 1;
-        self.back += 1;
+        self.back = (self.back + 1) % self.fat.capacity;
         if self.size > (3 * self.fat.capacity) / 4 {
             self.resize(self.fat.capacity * 2);
         }
     }
     fn pop(&mut self) {
+        if self.size == 0 {
+            return;
+        }
         self.fat.update(&[(self.front, Value::identity())]);
         self.size -= 1;
-        self.front += 1;
-        if self.size <= self.fat.capacity / 4 {
+        self.front = (self.front + 1) % self.fat.capacity;
+        if self.size <= self.fat.capacity / 4 && self.fat.capacity / 2 >= self.min_capacity {
             self.resize(self.fat.capacity / 2);
         }
     }
     fn query(&self) -> Value {
-        if self.front > self.back {
+        if self.inverted() {
             self.fat
                 .suffix(self.front)
                 .operate(&self.fat.prefix(self.back))
@@ -89,4 +93,7 @@ where
             self.fat.aggregate()
         }
     }
+    fn len(&self) -> usize {
+        self.size
+    }
 }
@@ -0,0 +1,137 @@
+use alga::general::AbstractMonoid;
+use alga::general::Operator;
+use std::ops::Range;
+
+use crate::flat_fat::fat::FAT;
+use crate::flat_fat::flat_fat::FlatFAT;
+use crate::Count;
+use crate::MultiWindow;
+use crate::QueryId;
+
+/// A Reactive Aggregator which answers multiple FIFO queries over the same
+/// stream. Items are stored once, in a circular buffer of leaves which is
+/// large enough to hold the longest query. A query `start..end` aggregates the
+/// items which are `start` up to (but not including) `end` insertions old,
+/// i.e., `0..n` is the window of the `n` most recent items. Each query is
+/// answered by a prefix/suffix decomposition of the tree in O(log n) time,
+/// without requiring an invertible operator.
+#[derive(Debug)]
+pub struct MultiRA<Value, BinOp>
+where
+    Value: AbstractMonoid<BinOp> + Clone + std::fmt::Debug,
+    BinOp: Operator,
+{
+    fat: FlatFAT<Value, BinOp>,
+    /// Registered queries, indexed by id. Removed queries leave a hole.
+    queries: Vec<Option<Range<Count>>>,
+    aggs: Vec<Value>,
+    /// Leaf where the next item is inserted
+    back: usize,
+}
+
+impl<Value, BinOp> MultiRA<Value, BinOp>
+where
+    Value: AbstractMonoid<BinOp> + Clone + std::fmt::Debug,
+    BinOp: Operator,
+{
+    /// Recomputes the aggregate of a query
+    fn refresh(&mut self, id: QueryId) {
+        let (start, end) = match &self.queries[id] {
+            Some(query) => (query.start as usize, query.end as usize),
+            None => return,
+        };
+        if start >= end {
+            self.aggs[id] = Value::identity();
+            return;
+        }
+        let capacity = self.fat.capacity;
+        // Leaves of the oldest and newest items of the query
+        let oldest = (self.back + capacity - end) % capacity;
+        let newest = (self.back + capacity - 1 - start) % capacity;
+        self.aggs[id] = if oldest <= newest {
+            self.fat.range_query(oldest..newest + 1)
+        } else {
+            self.fat.suffix(oldest).operate(&self.fat.prefix(newest))
+        };
+    }
+    /// Grows the tree to hold at least `capacity` items, keeping the items
+    /// which are currently stored.
+    fn resize(&mut self, capacity: usize) {
+        let leaves = self.fat.leaves(0..self.fat.capacity);
+        let mut fat = FlatFAT::with_capacity(capacity);
+        let mut items = leaves[self.back..].to_vec();
+        items.extend_from_slice(&leaves[..self.back]);
+        fat.update_ordered(&items);
+        fat.update_parents();
+        self.back = items.len() % fat.capacity;
+        self.fat = fat;
+    }
+}
+
+impl<Value, BinOp> MultiWindow<Value, BinOp> for MultiRA<Value, BinOp>
+where
+    Value: AbstractMonoid<BinOp> + Clone + std::fmt::Debug,
+    BinOp: Operator,
+{
+    fn new(queries: &[Range<Count>]) -> Self {
+        assert!(
+            queries.iter().all(|query| 0 <= query.start),
+            "Queries must start at a non-negative offset"
+        );
+        let capacity = queries.iter().map(|query| query.end).max().unwrap_or(0);
+        Self {
+            fat: FlatFAT::with_capacity(capacity.max(1) as usize),
+            queries: queries.iter().cloned().map(Some).collect(),
+            aggs: vec![Value::identity(); queries.len()],
+            back: 0,
+        }
+    }
+    fn insert(&mut self, v: Value) {
+        self.fat.update(&[(self.back, v)]);
+        self.back = (self.back + 1) % self.fat.capacity;
+        for id in 0..self.queries.len() {
+            self.refresh(id);
+        }
+    }
+    fn query(&self, id: QueryId) -> Value {
+        assert!(self.queries[id].is_some(), "Query {} is not registered", id);
+        self.aggs[id].clone()
+    }
+    fn results<'a>(&'a self) -> impl Iterator<Item = (QueryId, &'a Value)>
+    where
+        Value: 'a,
+    {
+        self.queries
+            .iter()
+            .zip(self.aggs.iter())
+            .enumerate()
+            .filter(|(_, (query, _))| query.is_some())
+            .map(|(id, (_, agg))| (id, agg))
+    }
+    fn add_query(&mut self, range: Range<Count>) -> QueryId {
+        assert!(
+            0 <= range.start,
+            "Queries must start at a non-negative offset"
+        );
+        if range.end as usize > self.fat.capacity {
+            self.resize(range.end as usize);
+        }
+        let id = match self.queries.iter().position(Option::is_none) {
+            Some(id) => {
+                self.queries[id] = Some(range);
+                id
+            }
+            None => {
+                self.queries.push(Some(range));
+                self.aggs.push(Value::identity());
+                self.queries.len() - 1
+            }
+        };
+        self.refresh(id);
+        id
+    }
+    fn remove_query(&mut self, id: QueryId) {
+        self.queries[id] = None;
+        self.aggs[id] = Value::identity();
+    }
+}
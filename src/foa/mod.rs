@@ -1,130 +1,212 @@
 mod stack;
 use stack::List;
+pub use stack::{ArcK, PointerKind, RcK};
 
 use crate::FunctionalWindow;
-use alga::general::AbstractGroup;
+use alga::general::AbstractMonoid;
 use alga::general::Operator;
+use std::fmt;
 use std::marker::PhantomData;
 
-#[derive(Clone)]
+// Number of rotation steps which are performed per insert and evict. A
+// rotation with a front of n items takes at most 3n+1 steps, and must be
+// finished before the n items are evicted.
+const STEPS: usize = 3;
+
+#[derive(Clone, Debug)]
 pub struct Elem<T> {
     val: T,
     agg: T,
 }
 
-struct FOA<Value, BinOp>
+/// Functional Okasaki Aggregator. Every version of the window is immutable, and
+/// `insert` and `evict` return new versions which share structure with the old
+/// one. Like in Okasaki's real-time queues, the back is rotated into the front
+/// incrementally, so that every operation on every version is worst-case O(1).
+///
+/// The versions are linked by `Rc`s by default. With `ArcK` as pointer kind,
+/// they are linked by `Arc`s and can be shared between threads.
+pub struct FOA<Value, BinOp, P = RcK>
 where
-    Value: AbstractGroup<BinOp> + Clone,
+    Value: AbstractMonoid<BinOp> + Clone,
     BinOp: Operator,
+    P: PointerKind,
 {
-    front: List<Elem<Value>>,
-    next: List<Elem<Value>>,
-    back: List<Elem<Value>>,
+    // Oldest item first, where agg = val ⊕ ... ⊕ newest item of the front
+    front: List<Elem<Value>, P>,
+    front_len: usize,
+    // Newest item first, where agg = oldest item of the back ⊕ ... ⊕ val
+    back: List<Elem<Value>, P>,
+    back_len: usize,
+    // Rotation state, where `front ++ frozen` is being moved into `next`
+    rotating: bool,
+    // Part of the front which remains to be reversed into `reversed`
+    reversing: List<Elem<Value>, P>,
+    // Newest item first
+    reversed: List<Value, P>,
+    // Newest item first
+    frozen: List<Elem<Value>, P>,
+    frozen_agg: Value,
+    next: List<Elem<Value>, P>,
+    next_len: usize,
+    // Number of items of `reversed` which have not been evicted, and which
+    // remain to be moved into `next`
+    copying: usize,
     op: PhantomData<BinOp>,
 }
 
-impl<Value, BinOp> FunctionalWindow<Value, BinOp> for FOA<Value, BinOp>
+impl<Value, BinOp, P> Clone for FOA<Value, BinOp, P>
 where
-    Value: AbstractGroup<BinOp> + Clone,
+    Value: AbstractMonoid<BinOp> + Clone,
     BinOp: Operator,
+    P: PointerKind,
 {
-    fn new() -> FOA<Value, BinOp> {
+    fn clone(&self) -> Self {
         FOA {
-            front: List::empty(),
-            next: List::empty(),
-            back: List::empty(),
+            front: self.front.clone(),
+            front_len: self.front_len,
+            back: self.back.clone(),
+            back_len: self.back_len,
+            rotating: self.rotating,
+            reversing: self.reversing.clone(),
+            reversed: self.reversed.clone(),
+            frozen: self.frozen.clone(),
+            frozen_agg: self.frozen_agg.clone(),
+            next: self.next.clone(),
+            next_len: self.next_len,
+            copying: self.copying,
             op: PhantomData,
         }
     }
-    fn insert(&mut self, v: Value) -> FOA<Value, BinOp> {
+}
+
+impl<Value, BinOp, P> FunctionalWindow<Value, BinOp> for FOA<Value, BinOp, P>
+where
+    Value: AbstractMonoid<BinOp> + Clone,
+    BinOp: Operator,
+    P: PointerKind,
+{
+    fn new() -> FOA<Value, BinOp, P> {
         FOA {
-            front: self.front.clone(),
-            next: self.next.clone(),
-            back: self.back.cons(Elem {
-                agg: Self::agg(&self.back).operate(&v),
-                val: v,
-            }),
-            op: self.op,
+            front: List::empty(),
+            front_len: 0,
+            back: List::empty(),
+            back_len: 0,
+            rotating: false,
+            reversing: List::empty(),
+            reversed: List::empty(),
+            frozen: List::empty(),
+            frozen_agg: Value::identity(),
+            next: List::empty(),
+            next_len: 0,
+            copying: 0,
+            op: PhantomData,
         }
-        .makeq()
     }
-    fn evict(&mut self) -> FOA<Value, BinOp> {
-        FOA {
-            front: self.front.tail(),
-            next: self.next.clone(),
-            back: self.back.clone(),
-            op: self.op,
+    fn insert(&self, v: Value) -> FOA<Value, BinOp, P> {
+        let mut window = self.clone();
+        window.back = self.back.cons(Elem {
+            agg: Self::agg(&self.back).operate(&v),
+            val: v,
+        });
+        window.back_len += 1;
+        window.fixup();
+        window
+    }
+    fn evict(&self) -> FOA<Value, BinOp, P> {
+        let mut window = self.clone();
+        if !self.front.is_empty() {
+            window.front = self.front.tail();
+            window.front_len -= 1;
+            if window.rotating {
+                window.copying -= 1;
+            }
+            window.fixup();
         }
-        .makeq()
+        window
     }
     fn query(&self) -> Value {
-        Self::agg(&self.front).operate(&Self::agg(&self.back))
+        Self::agg(&self.front)
+            .operate(&self.frozen_agg)
+            .operate(&Self::agg(&self.back))
     }
 }
 
-impl<Value, BinOp> FOA<Value, BinOp>
+impl<Value, BinOp, P> FOA<Value, BinOp, P>
 where
-    Value: AbstractGroup<BinOp> + Clone,
+    Value: AbstractMonoid<BinOp> + Clone,
     BinOp: Operator,
+    P: PointerKind,
 {
-    fn agg(list: &List<Elem<Value>>) -> Value {
-        list.head().map(|elem| elem.agg).unwrap_or(Value::identity())
+    fn agg(list: &List<Elem<Value>, P>) -> Value {
+        list.head()
+            .map(|elem| elem.agg.clone())
+            .unwrap_or_else(Value::identity)
     }
-    fn makeq(&self) -> FOA<Value, BinOp> {
-        if self.next.is_empty() {
-            let front = Self::rot(FOA {
-                front: self.front.clone(),
-                next: self.back.clone(),
-                back: List::empty(),
-                op: self.op,
-            });
-            FOA {
-                next: front.clone(),
-                front,
-                back: List::empty(),
-                op: self.op,
-            }
-        } else {
-            FOA {
-                front: self.front.clone(),
-                next: self.next.tail(),
-                back: self.back.clone(),
-                op: self.op,
+    fn fixup(&mut self) {
+        if !self.rotating && self.back_len > self.front_len {
+            self.start_rotation();
+        }
+        for _ in 0..STEPS {
+            if self.rotating {
+                self.rotate();
             }
         }
     }
-    fn rot(self) -> List<Elem<Value>> {
-        let back = self.back.cons(
-            self.next
-                .head()
-                .map(|mut elem| {
-                    elem.agg = elem.val.operate(&Self::agg(&self.back));
-                    elem
-                })
-                .unwrap(),
-        );
-        if self.front.is_empty() {
-            back
-        } else {
-            FOA {
-                front: self.front.tail(),
-                next: self.next.tail(),
-                back,
-                op: self.op,
-            }
-            .rot()
-            .cons(
-                self.front
-                    .head()
-                    .map(|mut elem| {
-                        elem.agg = elem
-                            .val
-                            .operate(&Self::agg(&self.next))
-                            .operate(&Self::agg(&self.back));
-                        elem
-                    })
-                    .unwrap(),
-            )
+    fn start_rotation(&mut self) {
+        self.rotating = true;
+        self.reversing = self.front.clone();
+        self.frozen_agg = Self::agg(&self.back);
+        self.frozen = std::mem::replace(&mut self.back, List::empty());
+        self.back_len = 0;
+        self.copying = self.front_len;
+    }
+    // Performs one step of the rotation. The front is first reversed, so that
+    // both the frozen back and the front can be moved into the next front from
+    // the newest to the oldest item.
+    fn rotate(&mut self) {
+        if let Some(elem) = self.reversing.head() {
+            self.reversed = self.reversed.cons(elem.val.clone());
+            self.reversing = self.reversing.tail();
+        } else if let Some(elem) = self.frozen.head() {
+            self.next = self.next.cons(Elem {
+                agg: elem.val.operate(&Self::agg(&self.next)),
+                val: elem.val.clone(),
+            });
+            self.next_len += 1;
+            self.frozen = self.frozen.tail();
+        } else if self.copying > 0 {
+            let val = self.reversed.head().unwrap().clone();
+            self.next = self.next.cons(Elem {
+                agg: val.operate(&Self::agg(&self.next)),
+                val,
+            });
+            self.next_len += 1;
+            self.reversed = self.reversed.tail();
+            self.copying -= 1;
+        }
+        if self.reversing.is_empty() && self.frozen.is_empty() && self.copying == 0 {
+            self.front = std::mem::replace(&mut self.next, List::empty());
+            self.front_len = self.next_len;
+            self.next_len = 0;
+            self.reversed = List::empty();
+            self.frozen_agg = Value::identity();
+            self.rotating = false;
         }
     }
 }
+
+impl<Value, BinOp, P> fmt::Debug for FOA<Value, BinOp, P>
+where
+    Value: AbstractMonoid<BinOp> + Clone + fmt::Debug,
+    BinOp: Operator,
+    P: PointerKind,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FOA")
+            .field("front", &self.front)
+            .field("frozen", &self.frozen)
+            .field("back", &self.back)
+            .finish()
+    }
+}
@@ -1,83 +1,134 @@
+use std::fmt;
+use std::ops::Deref;
 use std::rc::Rc;
+use std::sync::Arc;
 
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub enum List<Value> {
-    Nil,
-    Cons(Value, Rc<List<Value>>),
+/// The kind of reference-counted pointer which links the nodes of a persistent
+/// list, i.e., `Rc` or `Arc`.
+pub trait PointerKind {
+    type Pointer<T>: Deref<Target = T> + Clone;
+    fn new<T>(v: T) -> Self::Pointer<T>;
+    fn try_unwrap<T>(ptr: Self::Pointer<T>) -> Result<T, Self::Pointer<T>>;
 }
 
-impl<Value: Clone> List<Value> {
-    pub fn empty() -> List<Value> {
-        List::Nil
+/// Pointer kind for lists which are only used by a single thread
+#[derive(Copy, Clone, Debug)]
+pub struct RcK;
+
+/// Pointer kind for lists which are shared between threads
+#[derive(Copy, Clone, Debug)]
+pub struct ArcK;
+
+impl PointerKind for RcK {
+    type Pointer<T> = Rc<T>;
+    fn new<T>(v: T) -> Rc<T> {
+        Rc::new(v)
+    }
+    fn try_unwrap<T>(ptr: Rc<T>) -> Result<T, Rc<T>> {
+        Rc::try_unwrap(ptr)
+    }
+}
+
+impl PointerKind for ArcK {
+    type Pointer<T> = Arc<T>;
+    fn new<T>(v: T) -> Arc<T> {
+        Arc::new(v)
+    }
+    fn try_unwrap<T>(ptr: Arc<T>) -> Result<T, Arc<T>> {
+        Arc::try_unwrap(ptr)
+    }
+}
+
+/// Persistent singly linked list, whose tails are shared between versions
+pub struct List<Value, P: PointerKind = RcK>(Option<P::Pointer<Node<Value, P>>>);
+
+struct Node<Value, P: PointerKind> {
+    head: Value,
+    tail: List<Value, P>,
+}
+
+impl<Value, P: PointerKind> List<Value, P> {
+    pub fn empty() -> List<Value, P> {
+        List(None)
     }
 
     pub fn is_empty(&self) -> bool {
-        match self {
-            List::Nil => true,
-            _ => false,
-        }
+        self.0.is_none()
+    }
+
+    pub fn cons(&self, x: Value) -> List<Value, P> {
+        List(Some(P::new(Node {
+            head: x,
+            tail: self.clone(),
+        })))
     }
 
-    pub fn cons(&self, x: Value) -> List<Value> {
-        List::Cons(x, Rc::new(self.clone()))
+    pub fn head(&self) -> Option<&Value> {
+        self.0.as_ref().map(|node| &node.head)
     }
 
-    pub fn head(&self) -> Option<Value> {
-        match self {
-            List::Cons(h, _) => Some(h.clone()),
-            List::Nil => None,
+    pub fn tail(&self) -> List<Value, P> {
+        match &self.0 {
+            Some(node) => node.tail.clone(),
+            None => List(None),
         }
     }
 
-    pub fn tail(&self) -> List<Value> {
-        match self {
-            List::Nil => List::Nil,
-            List::Cons(_, t) => (**t).clone(),
+    pub fn iter(&self) -> impl Iterator<Item = &Value> {
+        let mut list = self;
+        std::iter::from_fn(move || {
+            let node = list.0.as_ref()?;
+            list = &node.tail;
+            Some(&node.head)
+        })
+    }
+}
+
+impl<Value, P: PointerKind> Clone for List<Value, P> {
+    fn clone(&self) -> Self {
+        List(self.0.clone())
+    }
+}
+
+impl<Value: PartialEq, P: PointerKind> PartialEq for List<Value, P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<Value: Eq, P: PointerKind> Eq for List<Value, P> {}
+
+impl<Value: fmt::Debug, P: PointerKind> fmt::Debug for List<Value, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// Drops the nodes which are not shared with other lists iteratively, since the
+// recursive drop would overflow the stack for long lists.
+impl<Value, P: PointerKind> Drop for List<Value, P> {
+    fn drop(&mut self) {
+        let mut next = self.0.take();
+        while let Some(node) = next {
+            match P::try_unwrap(node) {
+                Ok(mut node) => next = node.tail.0.take(),
+                Err(_) => break,
+            }
         }
     }
 }
 
 #[test]
 fn list() {
-    //     let l1: List<usize> = Stack::new();
-    //     let l2 = l1.cons(3).cons(2).cons(1);
-    //     let l3 = l1.cons(5).cons(4);
-    //
-    //     assert!(l1.is_empty());
-    //     assert!(!l2.is_empty());
-    //     assert_eq!(
-    //         l2,
-    //         Cons(1, Rc::new(Cons(2, Rc::new(Cons(3, Rc::new(Nil))))))
-    //     );
-    //     assert_eq!(l2.head(), 1);
-    //     assert_eq!(l2.tail(), Cons(2, Rc::new(Cons(3, Rc::new(Nil)))));
-    //
-    //     assert_eq!(l1.append(&l2), l2);
-    //     assert_eq!(l2.append(&l1), l2);
-    //
-    //     assert_eq!(
-    //         l2.append(&l3),
-    //         Cons(
-    //             1,
-    //             Rc::new(Cons(
-    //                 2,
-    //                 Rc::new(Cons(3, Rc::new(Cons(4, Rc::new(Cons(5, Rc::new(Nil)))))))
-    //             ))
-    //         )
-    //     );
-    //
-    //     assert_eq!(
-    //         l3.append(&l2),
-    //         Cons(
-    //             4,
-    //             Rc::new(Cons(
-    //                 5,
-    //                 Rc::new(Cons(1, Rc::new(Cons(2, Rc::new(Cons(3, Rc::new(Nil)))))))
-    //             ))
-    //         )
-    //     );
-    //
-    //     assert_eq!(l3.update(0, 0), Cons(0, Rc::new(Cons(5, Rc::new(Nil)))));
-    //
-    //     assert_eq!(l3.update(1, 0), Cons(4, Rc::new(Cons(0, Rc::new(Nil)))));
+    let l1: List<usize> = List::empty();
+    let l2 = l1.cons(3).cons(2).cons(1);
+    let l3 = l2.tail().cons(4);
+
+    assert!(l1.is_empty());
+    assert!(!l2.is_empty());
+    assert_eq!(l2.head(), Some(&1));
+    assert_eq!(l2.tail(), List::empty().cons(3).cons(2));
+    assert_eq!(l3, List::empty().cons(3).cons(2).cons(4));
+    assert_eq!(l1.tail(), l1);
+    assert_eq!(format!("{:?}", l3), "[4, 2, 3]");
 }
@@ -0,0 +1,164 @@
+use crate::FifoWindow;
+use alga::general::AbstractMonoid;
+use alga::general::Operator;
+use std::marker::PhantomData;
+
+// Number of rotation steps which are performed per insert and evict. Two steps
+// suffice to finish a rotation before the front runs out of items, and before
+// the back has grown larger than the new front.
+const STEPS: usize = 2;
+
+#[derive(Debug)]
+struct Item<Value: Clone> {
+    agg: Value,
+    val: Value,
+}
+
+impl<Value: Clone> Item<Value> {
+    fn new(agg: Value, val: Value) -> Item<Value> {
+        Item { agg, val }
+    }
+}
+
+/// Imperative Okasaki Aggregator. Like Two-Stacks, the window is split into
+/// a front stack which is aggregated from the newest to the oldest item, and
+/// a back stack which is aggregated from the oldest to the newest item.
+/// Instead of flipping the back onto the front when the front is empty, a
+/// rotation starts as soon as the back grows larger than the front, and moves
+/// a constant number of items per operation into a new front. This bounds the
+/// number of operator calls per insert and evict by O(1) in the worst case.
+#[derive(Debug)]
+pub struct IOA<Value, BinOp>
+where
+    Value: AbstractMonoid<BinOp> + Clone,
+    BinOp: Operator,
+{
+    // front[i].agg = front[i].val + ... + front[0].val, where front.last() is the oldest item
+    front: Vec<Item<Value>>,
+    // back[i] is the ith oldest item of the back
+    back: Vec<Value>,
+    // back[0] + ... + back[back.len()-1]
+    back_agg: Value,
+    // Rotation state, where `front ++ frozen` is being moved into `next`
+    rotating: bool,
+    next: Vec<Item<Value>>,
+    frozen: Vec<Value>,
+    frozen_agg: Value,
+    // Number of items at the bottom of `front` which have been moved into `next`
+    copied: usize,
+    // Items of the previous front, which are dropped a few per operation
+    stale: Vec<Item<Value>>,
+    op: PhantomData<BinOp>,
+}
+
+impl<Value, BinOp> FifoWindow<Value, BinOp> for IOA<Value, BinOp>
+where
+    Value: AbstractMonoid<BinOp> + Clone,
+    BinOp: Operator,
+{
+    fn new() -> IOA<Value, BinOp> {
+        Self::with_capacity(0)
+    }
+    // The front, the next front, and the stale front never hold more than all
+    // items, and the frozen back and the back never hold more than half of
+    // them.
+    fn with_capacity(capacity: usize) -> IOA<Value, BinOp> {
+        IOA {
+            front: Vec::with_capacity(capacity),
+            back: Vec::with_capacity(capacity / 2),
+            back_agg: Value::identity(),
+            rotating: false,
+            next: Vec::with_capacity(capacity),
+            frozen: Vec::with_capacity(capacity / 2),
+            frozen_agg: Value::identity(),
+            copied: 0,
+            stale: Vec::with_capacity(capacity),
+            op: PhantomData,
+        }
+    }
+    fn push(&mut self, v: Value) {
+        self.back_agg = self.back_agg.operate(&v);
+        self.back.push(v);
+        self.fixup();
+    }
+    fn pop(&mut self) {
+        if self.front.pop().is_some() {
+            self.fixup();
+        }
+    }
+    fn query(&self) -> Value {
+        Self::agg(&self.front)
+            .operate(&self.frozen_agg)
+            .operate(&self.back_agg)
+    }
+    // Items of the frozen back which have been moved into the next front are
+    // still counted by `next.len() - copied`.
+    fn len(&self) -> usize {
+        self.front.len() + self.frozen.len() + self.next.len() - self.copied + self.back.len()
+    }
+}
+
+impl<Value, BinOp> IOA<Value, BinOp>
+where
+    Value: AbstractMonoid<BinOp> + Clone,
+    BinOp: Operator,
+{
+    #[inline(always)]
+    fn agg(stack: &[Item<Value>]) -> Value {
+        if let Some(top) = stack.last() {
+            top.agg.clone()
+        } else {
+            Value::identity()
+        }
+    }
+    fn fixup(&mut self) {
+        if !self.rotating && self.back.len() > self.front.len() {
+            self.start_rotation();
+        }
+        for _ in 0..STEPS {
+            self.stale.pop();
+            if self.rotating {
+                self.rotate();
+            }
+        }
+    }
+    // Freezes the back, so that `front ++ frozen` can be moved into the next
+    // front, and starts a new, empty back.
+    fn start_rotation(&mut self) {
+        self.rotating = true;
+        std::mem::swap(&mut self.frozen, &mut self.back);
+        self.frozen_agg = std::mem::replace(&mut self.back_agg, Value::identity());
+        self.copied = 0;
+    }
+    // Moves one item into the next front, from the newest to the oldest. The
+    // frozen back is moved first, and then the front from the bottom up. Items
+    // are evicted from the top of the front meanwhile, so the rotation is
+    // finished when it reaches the top. Items of the front whose values have
+    // been moved are never queried, since they are below the top.
+    fn rotate(&mut self) {
+        let val = if let Some(val) = self.frozen.pop() {
+            Some(val)
+        } else if self.copied < self.front.len() {
+            self.copied += 1;
+            let item = &mut self.front[self.copied - 1];
+            Some(std::mem::replace(&mut item.val, Value::identity()))
+        } else {
+            None
+        };
+        if let Some(val) = val {
+            let agg = val.operate(&Self::agg(&self.next));
+            self.next.push(Item::new(agg, val));
+        }
+        if self.frozen.is_empty() && self.copied == self.front.len() {
+            // The previous front holds at most as many items as the new front,
+            // so it has been dropped before the next rotation finishes, and
+            // its buffer can be reused for the next front
+            debug_assert!(self.stale.is_empty());
+            std::mem::swap(&mut self.front, &mut self.next);
+            std::mem::swap(&mut self.next, &mut self.stale);
+            self.copied = 0;
+            self.frozen_agg = Value::identity();
+            self.rotating = false;
+        }
+    }
+}
@@ -0,0 +1,115 @@
+pub mod ttl;
+
+use crate::OutOfOrder;
+use crate::TimeWindow;
+use alga::general::Operator;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+/// Time windows partitioned by key, e.g., FIBAs or Two-Stacks in a `TimeFifo`.
+/// Each key has its own window, which is created by the first insert of the
+/// key and dropped when it becomes empty. Evictions are global: an index of the
+/// oldest time of each key, in time order, makes it possible to only visit the
+/// keys which have values to evict.
+pub struct Keyed<Key, Time, Value, BinOp, Window>
+where
+    Key: Hash + Ord + Clone,
+    Time: Ord + Copy,
+    BinOp: Operator,
+    Window: TimeWindow<Time, Value, BinOp>,
+{
+    windows: HashMap<Key, Window>,
+    // Oldest time of the window of each key
+    oldest: BTreeSet<(Time, Key)>,
+    op: PhantomData<(Value, BinOp)>,
+}
+
+impl<Key, Time, Value, BinOp, Window> Keyed<Key, Time, Value, BinOp, Window>
+where
+    Key: Hash + Ord + Clone,
+    Time: Ord + Copy,
+    BinOp: Operator,
+    Window: TimeWindow<Time, Value, BinOp>,
+{
+    pub fn new() -> Self {
+        Self {
+            windows: HashMap::new(),
+            oldest: BTreeSet::new(),
+            op: PhantomData,
+        }
+    }
+    /// Inserts a value at time `t` into the window of `key`, or returns it in
+    /// an error if the window can not accept values at time `t`
+    pub fn insert(&mut self, key: Key, t: Time, v: Value) -> Result<(), OutOfOrder<Time, Value>> {
+        let window = self.windows.entry(key.clone()).or_insert_with(Window::new);
+        let oldest = window.oldest();
+        let result = window.insert(t, v);
+        self.reindex(key, oldest);
+        result
+    }
+    /// Returns the aggregate of the window of `key`, if it has values
+    pub fn query(&self, key: &Key) -> Option<Value> {
+        self.windows.get(key).map(|window| window.query())
+    }
+    /// Returns the aggregate of the window of each key, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&Key, Value)> + '_ {
+        self.windows
+            .iter()
+            .map(|(key, window)| (key, window.query()))
+    }
+    /// Removes the window of `key`, and returns its aggregate if it had values
+    pub fn remove(&mut self, key: &Key) -> Option<Value> {
+        let window = self.windows.remove(key)?;
+        if let Some(oldest) = window.oldest() {
+            self.oldest.remove(&(oldest, key.clone()));
+        }
+        Some(window.query())
+    }
+    /// Evicts all values at times before `t` from the windows of all keys
+    pub fn evict_until(&mut self, t: Time) {
+        while let Some((oldest, key)) = self.oldest.iter().next().cloned() {
+            if t <= oldest {
+                break;
+            }
+            self.windows.get_mut(&key).unwrap().evict(t);
+            self.reindex(key, Some(oldest));
+        }
+    }
+    /// Returns the number of keys whose windows have values
+    pub fn len(&self) -> usize {
+        self.windows.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+    // Moves the key in the index from its previous oldest time to the oldest
+    // time of its window, and drops the window if it is empty
+    fn reindex(&mut self, key: Key, previous: Option<Time>) {
+        let oldest = self.windows[&key].oldest();
+        if previous != oldest {
+            if let Some(previous) = previous {
+                self.oldest.remove(&(previous, key.clone()));
+            }
+            if let Some(oldest) = oldest {
+                self.oldest.insert((oldest, key.clone()));
+            }
+        }
+        if oldest.is_none() {
+            self.windows.remove(&key);
+        }
+    }
+}
+
+impl<Key, Time, Value, BinOp, Window> Default for Keyed<Key, Time, Value, BinOp, Window>
+where
+    Key: Hash + Ord + Clone,
+    Time: Ord + Copy,
+    BinOp: Operator,
+    Window: TimeWindow<Time, Value, BinOp>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,164 @@
+use crate::keyed::Keyed;
+use crate::OutOfOrder;
+use crate::TimeWindow;
+use alga::general::Operator;
+use std::cell::Cell;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::rc::Rc;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Source of processing time, as opposed to the event time of values
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// Clock which reads the time of the system
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Clock which only advances when told to, e.g., in tests. Clones of a manual
+/// clock share the same time.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Rc<Cell<Instant>>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self {
+            now: Rc::new(Cell::new(Instant::now())),
+        }
+    }
+    /// Advances the time of the clock by `duration`
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.now.get()
+    }
+}
+
+/// Keyed windows whose keys expire when no values have been inserted into them
+/// for longer than a time to live, measured in processing time. An index of the
+/// keys in order of their last insert makes it possible to only visit the keys
+/// which expire. The final aggregate of each expired key may be passed to a
+/// callback.
+pub struct Expiring<Key, Time, Value, BinOp, Window, C>
+where
+    Key: Hash + Ord + Clone,
+    Time: Ord + Copy,
+    BinOp: Operator,
+    Window: TimeWindow<Time, Value, BinOp>,
+    C: Clock,
+{
+    keyed: Keyed<Key, Time, Value, BinOp, Window>,
+    clock: C,
+    ttl: Duration,
+    // Processing time of the last insert into each key
+    inserted: HashMap<Key, Instant>,
+    // Keys in order of their last insert
+    idle: BTreeSet<(Instant, Key)>,
+    on_expiry: Option<Box<dyn FnMut(Key, Value)>>,
+}
+
+impl<Key, Time, Value, BinOp, Window, C> Expiring<Key, Time, Value, BinOp, Window, C>
+where
+    Key: Hash + Ord + Clone,
+    Time: Ord + Copy,
+    BinOp: Operator,
+    Window: TimeWindow<Time, Value, BinOp>,
+    C: Clock,
+{
+    /// Returns keyed windows whose keys expire after being idle for longer
+    /// than `ttl` according to `clock`
+    pub fn new(ttl: Duration, clock: C) -> Self {
+        Self {
+            keyed: Keyed::new(),
+            clock,
+            ttl,
+            inserted: HashMap::new(),
+            idle: BTreeSet::new(),
+            on_expiry: None,
+        }
+    }
+    /// Sets a callback which receives each expired key with its final aggregate
+    pub fn on_expiry(&mut self, callback: impl FnMut(Key, Value) + 'static) {
+        self.on_expiry = Some(Box::new(callback));
+    }
+    /// Expires idle keys, and inserts a value at time `t` into the window of
+    /// `key`, or returns it in an error if the window can not accept values at
+    /// time `t`
+    pub fn insert(&mut self, key: Key, t: Time, v: Value) -> Result<(), OutOfOrder<Time, Value>> {
+        let now = self.clock.now();
+        self.expire_at(now);
+        // Rejected values do not refresh the key
+        self.keyed.insert(key.clone(), t, v)?;
+        if let Some(previous) = self.inserted.insert(key.clone(), now) {
+            self.idle.remove(&(previous, key.clone()));
+        }
+        self.idle.insert((now, key));
+        Ok(())
+    }
+    /// Removes the keys which have been idle for longer than the time to live,
+    /// and returns how many of them had values
+    pub fn expire(&mut self) -> usize {
+        let now = self.clock.now();
+        self.expire_at(now)
+    }
+    /// Returns the aggregate of the window of `key`, if it has values
+    pub fn query(&self, key: &Key) -> Option<Value> {
+        self.keyed.query(key)
+    }
+    /// Returns the aggregate of the window of each key, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&Key, Value)> + '_ {
+        self.keyed.iter()
+    }
+    /// Evicts all values at times before `t` from the windows of all keys
+    pub fn evict_until(&mut self, t: Time) {
+        self.keyed.evict_until(t);
+    }
+    /// Returns the number of keys whose windows have values
+    pub fn len(&self) -> usize {
+        self.keyed.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.keyed.is_empty()
+    }
+    // Keys whose windows became empty through evictions are still in the
+    // index, but they have no aggregate to pass to the callback.
+    fn expire_at(&mut self, now: Instant) -> usize {
+        let mut expired = 0;
+        while let Some((inserted, key)) = self.idle.iter().next().cloned() {
+            if now <= inserted + self.ttl {
+                break;
+            }
+            self.idle.remove(&(inserted, key.clone()));
+            self.inserted.remove(&key);
+            if let Some(agg) = self.keyed.remove(&key) {
+                expired += 1;
+                if let Some(callback) = &mut self.on_expiry {
+                    callback(key, agg);
+                }
+            }
+        }
+        expired
+    }
+}
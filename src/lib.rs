@@ -1,21 +1,44 @@
 use alga::general::Operator;
+use std::fmt;
 use std::ops::Range;
 
 pub type Count = i32;
+pub type QueryId = usize;
 pub(crate) type Uid = u32;
 
 // const NEG_INFINITY: Time = i32::MIN;
 // const POS_INFINITY: Time = i32::MAX;
 
+/// Error returned when a value is inserted at a time which a window can no
+/// longer accept. It contains the rejected time and value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutOfOrder<Time, Value> {
+    pub time: Time,
+    pub value: Value,
+}
+
+impl<Time: fmt::Debug, Value> fmt::Display for OutOfOrder<Time, Value> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "value at time {:?} is out of order", self.time)
+    }
+}
+
+impl<Time: fmt::Debug, Value: fmt::Debug> std::error::Error for OutOfOrder<Time, Value> {}
+
 pub trait TimeWindow<Time, Value, BinOp>
 where
     Time: Ord,
     BinOp: Operator,
 {
     fn new() -> Self;
-    fn insert(&mut self, t: Time, v: Value);
+    /// Inserts a value at time `t`, or returns it in an error if the window
+    /// can not accept values at time `t`
+    fn insert(&mut self, t: Time, v: Value) -> Result<(), OutOfOrder<Time, Value>>;
+    /// Evicts all values at times before `t`
     fn evict(&mut self, t: Time);
     fn query(&self) -> Value;
+    /// Returns the time of the oldest value in the window, if it is not empty
+    fn oldest(&self) -> Option<Time>;
 }
 
 pub trait TimeWindowExtra<Time, Value, BinOp>
@@ -23,7 +46,11 @@ where
     Time: Ord,
     BinOp: Operator,
 {
-    fn range_query(&self, range: Range<Time>) -> Value;
+    /// Aggregates the values at times at or after `range.start` and before
+    /// `range.end`
+    fn range_query(&mut self, range: Range<Time>) -> Value;
+    /// Returns the oldest time at or after `t` in the window, if any
+    fn oldest_since(&self, t: Time) -> Option<Time>;
 }
 
 pub trait FifoWindow<Value, BinOp>
@@ -31,17 +58,36 @@ where
     BinOp: Operator,
 {
     fn new() -> Self;
+    /// Returns a window which can hold `capacity` values without reallocating
+    fn with_capacity(capacity: usize) -> Self;
     fn push(&mut self, v: Value);
     fn pop(&mut self);
     fn query(&self) -> Value;
+    /// Returns the number of values in the window
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
 
 pub trait MultiWindow<Value, BinOp>
 where
     BinOp: Operator,
 {
+    /// Returns a window which answers the queries with ids `0..ranges.len()`
     fn new(ranges: &[Range<Count>]) -> Self;
     fn insert(&mut self, v: Value);
+    /// Returns the aggregate of a query
+    fn query(&self, id: QueryId) -> Value;
+    /// Returns the aggregates of all registered queries
+    fn results<'a>(&'a self) -> impl Iterator<Item = (QueryId, &'a Value)>
+    where
+        Value: 'a;
+    /// Registers a new query over the current contents of the window. Items
+    /// which were too old to be kept by the window are not included.
+    fn add_query(&mut self, range: Range<Count>) -> QueryId;
+    /// Unregisters a query, whose id may then be reused by `add_query`
+    fn remove_query(&mut self, id: QueryId);
 }
 
 pub trait FunctionalWindow<Value, BinOp>
@@ -49,8 +95,10 @@ where
     BinOp: Operator,
 {
     fn new() -> Self;
-    fn insert(&mut self, v: Value) -> Self;
-    fn evict(&mut self) -> Self;
+    /// Returns a new version of the window with `v` inserted
+    fn insert(&self, v: Value) -> Self;
+    /// Returns a new version of the window with the oldest value evicted
+    fn evict(&self) -> Self;
     fn query(&self) -> Value;
 }
 
@@ -58,15 +106,35 @@ where
 pub mod fiba;
 // Two-Stacks
 pub mod two_stacks;
+// Two-Stacks Lite
+pub mod two_stacks_lite;
 // Subtract-On-Evict
 pub mod soe;
 // Recalculate-From-Scratch
 pub mod rfs;
 // Functional Okasaki Aggregator
 pub mod foa;
+// Imperative Okasaki Aggregator
+pub mod ioa;
 // De-Amortized Banker's Aggregator
 pub mod daba;
+// De-Amortized Banker's Aggregator Lite
+pub mod daba_lite;
 // Slide Side (TwoStacks with shared windows)
 pub mod slide_side;
+// SlickDeque (multiple windows with running aggregates or monotonic deques)
+pub mod slick_deque;
 // FlatFAT (flat fixed-size aggregator)
 pub mod flat_fat;
+// Operator adapters
+pub mod ops;
+// Count-based windows on top of FIFO windows
+pub mod count_window;
+// Time-based windows on top of FIFO windows
+pub mod time_fifo;
+// Time-based windows with bounded out-of-order arrivals
+pub mod bounded_disorder;
+// Window assigners and emission of window aggregates
+pub mod windowing;
+// Time-based windows partitioned by key
+pub mod keyed;
@@ -0,0 +1,149 @@
+use alga::general::AbstractMagma;
+use alga::general::AbstractMonoid;
+use alga::general::AbstractSemigroup;
+use alga::general::Identity;
+use alga::general::Operator;
+use std::marker::PhantomData;
+
+/// A value which may or may not yet be assigned. Lifts any semigroup `(Value,
+/// BinOp)` into a monoid `(Optional<Value, BinOp>, BinOp)` whose identity is
+/// the unassigned value. This makes it possible to aggregate semigroups which
+/// lack an identity, e.g., min/max over unbounded types or first/last, with
+/// every window which requires a monoid.
+///
+/// Unassigned values are skipped when combining, so the order of assigned
+/// values is preserved and non-commutative operators stay correct.
+pub struct Optional<Value, BinOp> {
+    value: Option<Value>,
+    binop: PhantomData<BinOp>,
+}
+
+impl<Value, BinOp> Optional<Value, BinOp> {
+    /// Returns an assigned value
+    pub fn new(value: Value) -> Self {
+        Self::from(Some(value))
+    }
+    /// Returns the unassigned value
+    pub fn none() -> Self {
+        Self::from(None)
+    }
+    /// Returns true if the value is assigned
+    pub fn is_some(&self) -> bool {
+        self.value.is_some()
+    }
+    /// Returns true if the value is unassigned
+    pub fn is_none(&self) -> bool {
+        self.value.is_none()
+    }
+    /// Returns a reference to the value, if assigned
+    pub fn get(&self) -> Option<&Value> {
+        self.value.as_ref()
+    }
+    /// Returns the value, if assigned
+    pub fn into_inner(self) -> Option<Value> {
+        self.value
+    }
+}
+
+impl<Value, BinOp> Optional<Value, BinOp>
+where
+    Value: Identity<BinOp> + Clone,
+    BinOp: Operator,
+{
+    /// Returns the value, or the identity of `BinOp` if unassigned
+    pub fn get_or_identity(&self) -> Value {
+        self.value.clone().unwrap_or_else(Value::identity)
+    }
+}
+
+impl<Value, BinOp> From<Value> for Optional<Value, BinOp> {
+    fn from(value: Value) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<Value, BinOp> From<Option<Value>> for Optional<Value, BinOp> {
+    fn from(value: Option<Value>) -> Self {
+        Self {
+            value,
+            binop: PhantomData,
+        }
+    }
+}
+
+impl<Value, BinOp> From<Optional<Value, BinOp>> for Option<Value> {
+    fn from(optional: Optional<Value, BinOp>) -> Self {
+        optional.value
+    }
+}
+
+impl<Value: Clone, BinOp> Clone for Optional<Value, BinOp> {
+    fn clone(&self) -> Self {
+        Self::from(self.value.clone())
+    }
+}
+
+impl<Value: Copy, BinOp> Copy for Optional<Value, BinOp> {}
+
+impl<Value: PartialEq, BinOp> PartialEq for Optional<Value, BinOp> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<Value: Eq, BinOp> Eq for Optional<Value, BinOp> {}
+
+impl<Value: std::hash::Hash, BinOp> std::hash::Hash for Optional<Value, BinOp> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.value.hash(state)
+    }
+}
+
+impl<Value: std::fmt::Debug, BinOp> std::fmt::Debug for Optional<Value, BinOp> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_tuple("Optional").field(&self.value).finish()
+    }
+}
+
+impl<Value, BinOp> Default for Optional<Value, BinOp> {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl<Value, BinOp> Identity<BinOp> for Optional<Value, BinOp>
+where
+    BinOp: Operator,
+{
+    fn identity() -> Self {
+        Self::none()
+    }
+}
+
+impl<Value, BinOp> AbstractMagma<BinOp> for Optional<Value, BinOp>
+where
+    Value: AbstractMagma<BinOp>,
+    BinOp: Operator,
+{
+    fn operate(&self, other: &Self) -> Self {
+        Self::from(match (self.value.as_ref(), other.value.as_ref()) {
+            (Some(a), Some(b)) => Some(a.operate(b)),
+            (Some(x), None) | (None, Some(x)) => Some(x.clone()),
+            (None, None) => None,
+        })
+    }
+}
+
+impl<Value, BinOp> AbstractSemigroup<BinOp> for Optional<Value, BinOp>
+where
+    Value: AbstractSemigroup<BinOp>,
+    BinOp: Operator,
+{
+}
+
+impl<Value, BinOp> AbstractMonoid<BinOp> for Optional<Value, BinOp>
+where
+    Value: AbstractSemigroup<BinOp>,
+    BinOp: Operator,
+{
+}
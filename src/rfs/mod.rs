@@ -1,8 +1,8 @@
 use crate::FifoWindow;
 use alga::general::AbstractMonoid;
 use alga::general::Operator;
-use std::marker::PhantomData;
 use std::collections::VecDeque;
+use std::marker::PhantomData;
 
 #[derive(Debug)]
 pub struct RFS<Value, BinOp>
@@ -25,6 +25,12 @@ where
             op: PhantomData,
         }
     }
+    fn with_capacity(capacity: usize) -> RFS<Value, BinOp> {
+        RFS {
+            stack: VecDeque::with_capacity(capacity),
+            op: PhantomData,
+        }
+    }
     fn push(&mut self, v: Value) {
         self.stack.push_back(v);
     }
@@ -34,6 +40,9 @@ where
     fn query(&self) -> Value {
         self.stack
             .iter()
-            .fold(Value::identity(), |acc, elem| acc.operate(&elem))
+            .fold(Value::identity(), |acc, elem| acc.operate(elem))
+    }
+    fn len(&self) -> usize {
+        self.stack.len()
     }
 }
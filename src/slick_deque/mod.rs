@@ -0,0 +1,371 @@
+// Shein, A.U., Chrysanthis, P.K. and Labrinidis, A., 2018. SlickDeque: High
+// Throughput and Low Latency Incremental Sliding-Window Aggregation. In EDBT
+// (pp. 397-408).
+use crate::Count;
+use crate::MultiWindow;
+use crate::QueryId;
+use alga::general::AbstractGroup;
+use alga::general::AbstractMonoid;
+use alga::general::Operator;
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::ops::Range;
+
+/// SlickDeque for invertible operators. A query `start..end` aggregates the
+/// items which are `start` up to (but not including) `end` insertions old.
+/// Every query keeps a running aggregate, which is updated in O(1) time per
+/// insert by adding the item which enters the query's range and subtracting
+/// the item which leaves it.
+pub struct SlickDequeInv<Value, BinOp>
+where
+    Value: AbstractGroup<BinOp> + Clone,
+    BinOp: Operator,
+{
+    // Circular buffer of the `window_size` most recent items
+    elems: Vec<Value>,
+    // Registered queries, indexed by id. Removed queries leave a hole.
+    queries: Vec<Option<Range<Count>>>,
+    aggs: Vec<Value>,
+    cur_pos: usize,
+    window_size: usize,
+    op: PhantomData<BinOp>,
+}
+
+impl<Value, BinOp> SlickDequeInv<Value, BinOp>
+where
+    Value: AbstractGroup<BinOp> + Clone,
+    BinOp: Operator,
+{
+    /// Returns the item which is `age` insertions old
+    fn elem(&self, age: usize) -> &Value {
+        &self.elems[(self.cur_pos + self.window_size - 1 - age) % self.window_size]
+    }
+    /// Aggregates the items of a query from scratch
+    fn aggregate(&self, range: &Range<Count>) -> Value {
+        (range.start..range.end)
+            .rev()
+            .fold(Value::identity(), |agg, age| {
+                agg.operate(self.elem(age as usize))
+            })
+    }
+    /// Grows the buffer to hold `window_size` items, keeping the items which
+    /// are currently stored.
+    fn resize(&mut self, window_size: usize) {
+        let mut elems = vec![Value::identity(); window_size - self.window_size];
+        elems.extend(self.elems.drain(self.cur_pos..));
+        elems.append(&mut self.elems);
+        self.elems = elems;
+        self.cur_pos = 0;
+        self.window_size = window_size;
+    }
+}
+
+impl<Value, BinOp> MultiWindow<Value, BinOp> for SlickDequeInv<Value, BinOp>
+where
+    Value: AbstractGroup<BinOp> + Clone,
+    BinOp: Operator,
+{
+    fn new(queries: &[Range<Count>]) -> Self {
+        assert!(
+            queries.iter().all(|query| 0 <= query.start),
+            "Queries must start at a non-negative offset"
+        );
+        let window_size = queries.iter().map(|query| query.end).max().unwrap_or(0);
+        let window_size = window_size.max(1) as usize;
+        Self {
+            elems: vec![Value::identity(); window_size],
+            queries: queries.iter().cloned().map(Some).collect(),
+            aggs: vec![Value::identity(); queries.len()],
+            cur_pos: 0,
+            window_size,
+            op: PhantomData,
+        }
+    }
+    fn insert(&mut self, v: Value) {
+        // The oldest item is overwritten, it only left the longest queries
+        let evicted = std::mem::replace(&mut self.elems[self.cur_pos], v);
+        self.cur_pos = (self.cur_pos + 1) % self.window_size;
+        for id in 0..self.queries.len() {
+            if let Some(Range { start, end }) = self.queries[id] {
+                if start < end {
+                    let (start, end) = (start as usize, end as usize);
+                    // The item at age `end` left the range and the item at
+                    // age `start` entered it
+                    let old = if end < self.window_size {
+                        self.elem(end)
+                    } else {
+                        &evicted
+                    };
+                    let agg = old
+                        .two_sided_inverse()
+                        .operate(&self.aggs[id])
+                        .operate(self.elem(start));
+                    self.aggs[id] = agg;
+                }
+            }
+        }
+    }
+    fn query(&self, id: QueryId) -> Value {
+        assert!(self.queries[id].is_some(), "Query {} is not registered", id);
+        self.aggs[id].clone()
+    }
+    fn results<'a>(&'a self) -> impl Iterator<Item = (QueryId, &'a Value)>
+    where
+        Value: 'a,
+    {
+        self.queries
+            .iter()
+            .zip(self.aggs.iter())
+            .enumerate()
+            .filter(|(_, (query, _))| query.is_some())
+            .map(|(id, (_, agg))| (id, agg))
+    }
+    fn add_query(&mut self, range: Range<Count>) -> QueryId {
+        assert!(
+            0 <= range.start,
+            "Queries must start at a non-negative offset"
+        );
+        if range.end as usize > self.window_size {
+            self.resize(range.end as usize);
+        }
+        let agg = self.aggregate(&range);
+        match self.queries.iter().position(Option::is_none) {
+            Some(id) => {
+                self.queries[id] = Some(range);
+                self.aggs[id] = agg;
+                id
+            }
+            None => {
+                self.queries.push(Some(range));
+                self.aggs.push(agg);
+                self.queries.len() - 1
+            }
+        }
+    }
+    fn remove_query(&mut self, id: QueryId) {
+        self.queries[id] = None;
+        self.aggs[id] = Value::identity();
+    }
+}
+
+/// SlickDeque for selection operators such as max and min, where `a + b` is
+/// always either `a` or `b`. Items are kept in a deque ordered by age, and an
+/// item is removed as soon as a newer item dominates it, i.e., `old + new ==
+/// new`. The aggregate of a query `0..n` is then the oldest item in the deque
+/// which is less than `n` insertions old. Every query keeps a cursor to that
+/// item, which only moves forward, so that each insert costs amortized O(1)
+/// time per query.
+///
+/// A new item may dominate items which are older than the start of a range, so
+/// queries which start at the same offset `start` share a deque of their own,
+/// which receives each item once it is `start` insertions old.
+pub struct SlickDequeNonInv<Value, BinOp>
+where
+    Value: AbstractMonoid<BinOp> + Clone,
+    BinOp: Operator,
+{
+    // Deque of each offset at which a query starts
+    lanes: Vec<Lane<Value>>,
+    // The `window_size` most recent items, which have not reached all lanes
+    // and which lanes of new queries start from
+    recent: VecDeque<Value>,
+    // Number of items which have been inserted
+    count: usize,
+    // Registered queries, indexed by id. Removed queries leave a hole.
+    queries: Vec<Option<Range<Count>>>,
+    // Lane of each query, and the position of its aggregate in the lane's
+    // deque, offset by the lane's `popped`
+    cursors: Vec<(usize, usize)>,
+    identity: Value,
+    window_size: usize,
+    op: PhantomData<BinOp>,
+}
+
+// Items which are at least `delay` insertions old
+struct Lane<Value> {
+    delay: usize,
+    // Items which are not dominated by newer items, and their positions in the stream
+    deque: VecDeque<(usize, Value)>,
+    // Number of items which have been popped from the front of the deque
+    popped: usize,
+}
+
+impl<Value, BinOp> SlickDequeNonInv<Value, BinOp>
+where
+    Value: AbstractMonoid<BinOp> + Clone,
+    BinOp: Operator,
+{
+    /// Returns the aggregate of a query
+    fn agg(&self, id: QueryId) -> &Value {
+        match &self.queries[id] {
+            Some(query) if query.start < query.end => {
+                let (lane, cursor) = self.cursors[id];
+                let lane = &self.lanes[lane];
+                match lane.deque.get(cursor - lane.popped) {
+                    Some((_, agg)) => agg,
+                    None => &self.identity,
+                }
+            }
+            _ => &self.identity,
+        }
+    }
+    /// Returns the position in the deque of a lane of the oldest item which is
+    /// less than `end` insertions old
+    fn search(&self, lane: usize, end: usize) -> usize {
+        let lane = &self.lanes[lane];
+        lane.popped
+            + lane
+                .deque
+                .partition_point(|(pos, _)| pos + end < self.count)
+    }
+    /// Returns the lane of the queries which start at `delay`. A new lane
+    /// starts from the recent items which are at least `delay` insertions old.
+    fn lane(&mut self, delay: usize) -> usize {
+        if let Some(lane) = self.lanes.iter().position(|lane| lane.delay == delay) {
+            return lane;
+        }
+        let mut lane = Lane {
+            delay,
+            deque: VecDeque::new(),
+            popped: 0,
+        };
+        let first = self.count - self.recent.len();
+        for (i, v) in self.recent.iter().enumerate() {
+            if first + i + delay < self.count {
+                lane.push(first + i, v.clone());
+            }
+        }
+        self.lanes.push(lane);
+        self.lanes.len() - 1
+    }
+}
+
+impl<Value: Clone> Lane<Value> {
+    fn push<BinOp>(&mut self, pos: usize, v: Value)
+    where
+        Value: AbstractMonoid<BinOp>,
+        BinOp: Operator,
+    {
+        while let Some((_, back)) = self.deque.back() {
+            if back.operate(&v) == v {
+                self.deque.pop_back();
+            } else {
+                break;
+            }
+        }
+        self.deque.push_back((pos, v));
+    }
+    // Pops the items which are too old for all queries
+    fn evict(&mut self, count: usize, window_size: usize) {
+        while let Some(&(front, _)) = self.deque.front() {
+            if front + window_size < count {
+                self.deque.pop_front();
+                self.popped += 1;
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+impl<Value, BinOp> MultiWindow<Value, BinOp> for SlickDequeNonInv<Value, BinOp>
+where
+    Value: AbstractMonoid<BinOp> + Clone,
+    BinOp: Operator,
+{
+    fn new(queries: &[Range<Count>]) -> Self {
+        assert!(
+            queries.iter().all(|query| 0 <= query.start),
+            "Queries must start at a non-negative offset"
+        );
+        let window_size = queries.iter().map(|query| query.end).max().unwrap_or(0);
+        let mut window = Self {
+            lanes: Vec::new(),
+            recent: VecDeque::new(),
+            count: 0,
+            queries: queries.iter().cloned().map(Some).collect(),
+            cursors: Vec::new(),
+            identity: Value::identity(),
+            window_size: window_size.max(1) as usize,
+            op: PhantomData,
+        };
+        window.cursors = queries
+            .iter()
+            .map(|query| (window.lane(query.start as usize), 0))
+            .collect();
+        window
+    }
+    fn insert(&mut self, v: Value) {
+        let pos = self.count;
+        self.count += 1;
+        self.recent.push_back(v);
+        let newest = self.recent.len() - 1;
+        for lane in self.lanes.iter_mut() {
+            // The item which has become `delay` insertions old
+            if lane.delay <= newest {
+                let v = self.recent[newest - lane.delay].clone();
+                lane.push(pos - lane.delay, v);
+            }
+            lane.evict(self.count, self.window_size);
+        }
+        if self.recent.len() > self.window_size {
+            self.recent.pop_front();
+        }
+        for id in 0..self.queries.len() {
+            if let Some(query) = &self.queries[id] {
+                let end = query.end as usize;
+                let (lane, cursor) = self.cursors[id];
+                let lane = &self.lanes[lane];
+                if lane.deque.is_empty() {
+                    continue;
+                }
+                let last = lane.popped + lane.deque.len() - 1;
+                // Items after the cursor may have been dominated by the new
+                // item, and items before it may have been evicted
+                let mut cursor = cursor.min(last).max(lane.popped);
+                while lane.deque[cursor - lane.popped].0 + end < self.count && cursor < last {
+                    cursor += 1;
+                }
+                self.cursors[id].1 = cursor;
+            }
+        }
+    }
+    fn query(&self, id: QueryId) -> Value {
+        assert!(self.queries[id].is_some(), "Query {} is not registered", id);
+        self.agg(id).clone()
+    }
+    fn results<'a>(&'a self) -> impl Iterator<Item = (QueryId, &'a Value)>
+    where
+        Value: 'a,
+    {
+        self.queries
+            .iter()
+            .enumerate()
+            .filter(|(_, query)| query.is_some())
+            .map(move |(id, _)| (id, self.agg(id)))
+    }
+    fn add_query(&mut self, range: Range<Count>) -> QueryId {
+        assert!(
+            0 <= range.start,
+            "Queries must start at a non-negative offset"
+        );
+        self.window_size = self.window_size.max(range.end as usize);
+        let lane = self.lane(range.start as usize);
+        let cursor = (lane, self.search(lane, range.end as usize));
+        match self.queries.iter().position(Option::is_none) {
+            Some(id) => {
+                self.queries[id] = Some(range);
+                self.cursors[id] = cursor;
+                id
+            }
+            None => {
+                self.queries.push(Some(range));
+                self.cursors.push(cursor);
+                self.queries.len() - 1
+            }
+        }
+    }
+    fn remove_query(&mut self, id: QueryId) {
+        self.queries[id] = None;
+    }
+}
@@ -1,20 +1,35 @@
 use crate::Count;
 use crate::MultiWindow;
+use crate::QueryId;
 use alga::general::AbstractGroup;
 use alga::general::Operator;
 use std::marker::PhantomData;
 use std::ops::Range;
 
+/// Answers multiple FIFO queries over the same stream. A query `start..end`
+/// aggregates the items which are `start` up to (but not including) `end`
+/// insertions old, i.e., `0..n` is the window of the `n` most recent items.
+///
+/// Items are stored in a circular buffer of `window_size` elements, which is
+/// the largest `end` of any query. The buffer is split into the current block,
+/// aggregated from left to right into `back`, and the previous block,
+/// aggregated from right to left into `front` whenever the buffer wraps
+/// around. Any query is then the combination of a suffix of the previous block
+/// and a prefix of the current block, where inverses are used to cut off the
+/// parts which are outside of the query.
 pub struct SlideSide<Value, BinOp>
 where
     Value: AbstractGroup<BinOp> + Clone,
     BinOp: Operator,
 {
+    // front[i] = elems[window_size-i] + ... + elems[window_size-1] (of the previous block)
     front: Vec<Value>,
+    // back[i] = elems[0] + ... + elems[i-1] (of the current block)
     back: Vec<Value>,
     elems: Vec<Value>,
-    queries: Vec<Range<Count>>,
-    pub aggs: Vec<Value>,
+    // Registered queries, indexed by id. Removed queries leave a hole.
+    queries: Vec<Option<Range<Count>>>,
+    aggs: Vec<Value>,
     cur_pos: usize,
     window_size: usize,
     op: PhantomData<BinOp>,
@@ -26,7 +41,12 @@ where
     BinOp: Operator,
 {
     fn new(queries: &[Range<Count>]) -> SlideSide<Value, BinOp> {
-        let window_size = queries.iter().map(window_size).max().unwrap();
+        assert!(
+            queries.iter().all(|query| 0 <= query.start),
+            "Queries must start at a non-negative offset"
+        );
+        let window_size = queries.iter().map(|query| query.end).max().unwrap_or(0);
+        let window_size = window_size.max(1) as usize;
         SlideSide {
             front: vec![Value::identity(); window_size + 1],
             back: vec![Value::identity(); window_size + 1],
@@ -34,45 +54,120 @@ where
             aggs: vec![Value::identity(); queries.len()],
             cur_pos: 0,
             window_size,
-            queries: queries.iter().cloned().collect::<Vec<Range<Count>>>(),
+            queries: queries.iter().cloned().map(Some).collect(),
             op: PhantomData,
         }
     }
     fn insert(&mut self, v: Value) {
         if self.cur_pos == 0 {
+            // The current block is full and becomes the previous block
             for i in 0..self.window_size {
-                self.front[i + 1] = self.front[i].operate(&self.elems[self.window_size - i + 1]);
+                self.front[i + 1] = self.elems[self.window_size - i - 1].operate(&self.front[i]);
             }
         }
+        self.back[self.cur_pos + 1] = self.back[self.cur_pos].operate(&v);
         self.elems[self.cur_pos] = v;
-        self.back[self.cur_pos + 1] = self.elems[self.cur_pos].operate(&self.back[self.cur_pos]);
         self.cur_pos = (self.cur_pos + 1) % self.window_size;
-
-        for (i, query) in self.queries.iter().enumerate() {
-            let cur_window_size = window_size(query);
-            let mut has_wrapped = false;
-            let mut end_ptr = self.cur_pos as isize;
-            if end_ptr == 0 {
-                end_ptr = self.window_size as isize;
-            }
-            let mut start_ptr = end_ptr - cur_window_size as isize;
-            if start_ptr < 0 {
-                has_wrapped = true;
-                start_ptr += self.window_size as isize;
+        for id in 0..self.queries.len() {
+            self.refresh(id);
+        }
+    }
+    fn query(&self, id: QueryId) -> Value {
+        assert!(self.queries[id].is_some(), "Query {} is not registered", id);
+        self.aggs[id].clone()
+    }
+    fn results<'a>(&'a self) -> impl Iterator<Item = (QueryId, &'a Value)>
+    where
+        Value: 'a,
+    {
+        self.queries
+            .iter()
+            .zip(self.aggs.iter())
+            .enumerate()
+            .filter(|(_, (query, _))| query.is_some())
+            .map(|(id, (_, agg))| (id, agg))
+    }
+    fn add_query(&mut self, range: Range<Count>) -> QueryId {
+        assert!(
+            0 <= range.start,
+            "Queries must start at a non-negative offset"
+        );
+        if range.end as usize > self.window_size {
+            self.resize(range.end as usize);
+        }
+        let id = match self.queries.iter().position(Option::is_none) {
+            Some(id) => {
+                self.queries[id] = Some(range);
+                id
             }
-            if !has_wrapped && start_ptr == 0 {
-                self.aggs[i] = self.back[end_ptr as usize].clone()
-            } else if has_wrapped {
-                self.aggs[i] = self.back[end_ptr as usize]
-                    .operate(&self.front[self.window_size - start_ptr as usize])
-            } else {
-                self.aggs[i] = self.back[end_ptr as usize]
-                    .operate(&self.back[start_ptr as usize].two_sided_inverse())
+            None => {
+                self.queries.push(Some(range));
+                self.aggs.push(Value::identity());
+                self.queries.len() - 1
             }
-        }
+        };
+        self.refresh(id);
+        id
+    }
+    fn remove_query(&mut self, id: QueryId) {
+        self.queries[id] = None;
+        self.aggs[id] = Value::identity();
     }
 }
 
-fn window_size(range: &Range<Count>) -> usize {
-    (range.end - range.start) as usize
+impl<Value, BinOp> SlideSide<Value, BinOp>
+where
+    Value: AbstractGroup<BinOp> + Clone,
+    BinOp: Operator,
+{
+    /// Recomputes the aggregate of a query
+    fn refresh(&mut self, id: QueryId) {
+        let (start, end) = match &self.queries[id] {
+            Some(query) => (query.start as usize, query.end as usize),
+            None => return,
+        };
+        if start >= end {
+            self.aggs[id] = Value::identity();
+            return;
+        }
+        // Number of items in the current block
+        let len = if self.cur_pos == 0 {
+            self.window_size
+        } else {
+            self.cur_pos
+        };
+        // Items of the previous block, from index window_size-(end-len) to
+        // window_size-(start-len)
+        let front = if end > len {
+            let from = end - len;
+            let to = start.saturating_sub(len);
+            self.front[from].operate(&self.front[to].two_sided_inverse())
+        } else {
+            Value::identity()
+        };
+        // Items of the current block, from index len-end to len-start
+        let back = if start < len {
+            let from = len.saturating_sub(end);
+            let to = len - start;
+            self.back[from].two_sided_inverse().operate(&self.back[to])
+        } else {
+            Value::identity()
+        };
+        self.aggs[id] = front.operate(&back);
+    }
+    /// Grows the buffer to hold `window_size` items. The items which are
+    /// currently stored become the end of a full current block.
+    fn resize(&mut self, window_size: usize) {
+        let mut elems = vec![Value::identity(); window_size - self.window_size];
+        elems.extend(self.elems.drain(self.cur_pos..));
+        elems.append(&mut self.elems);
+        self.back = vec![Value::identity(); window_size + 1];
+        for (i, elem) in elems.iter().enumerate() {
+            self.back[i + 1] = self.back[i].operate(elem);
+        }
+        self.front = vec![Value::identity(); window_size + 1];
+        self.elems = elems;
+        self.cur_pos = 0;
+        self.window_size = window_size;
+    }
 }
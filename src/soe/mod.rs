@@ -27,6 +27,13 @@ where
             op: PhantomData,
         }
     }
+    fn with_capacity(capacity: usize) -> SOE<Value, BinOp> {
+        SOE {
+            stack: VecDeque::with_capacity(capacity),
+            agg: Value::identity(),
+            op: PhantomData,
+        }
+    }
     fn push(&mut self, v: Value) {
         self.agg = self.agg.operate(&v);
         self.stack.push_back(v);
@@ -39,4 +46,7 @@ where
     fn query(&self) -> Value {
         self.agg.clone()
     }
+    fn len(&self) -> usize {
+        self.stack.len()
+    }
 }
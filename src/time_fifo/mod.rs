@@ -0,0 +1,106 @@
+use crate::daba::chunked_array_queue::ChunkedArrayQueue;
+use crate::daba::queue::Queue;
+use crate::FifoWindow;
+use crate::OutOfOrder;
+use crate::TimeWindow;
+use alga::general::Operator;
+use std::marker::PhantomData;
+
+/// Time-based sliding window on top of any FIFO window algorithm, for streams
+/// whose values arrive in time order. The time of each value is stored next to
+/// it in a queue, so that eviction by time is worst-case O(1) per value when
+/// the FIFO window is. A FIFO window with a fixed capacity evicts its oldest
+/// value when a value is pushed to it while it is full, and so does this
+/// window.
+#[derive(Debug)]
+pub struct TimeFifo<Time, Value, BinOp, Window>
+where
+    Time: Ord + Copy,
+    BinOp: Operator,
+    Window: FifoWindow<Value, BinOp>,
+{
+    window: Window,
+    // Time of the ith oldest value of the window
+    times: ChunkedArrayQueue<Time>,
+    // Newest time which has been inserted, even if it has been evicted
+    newest: Option<Time>,
+    op: PhantomData<(Value, BinOp)>,
+}
+
+impl<Time, Value, BinOp, Window> TimeWindow<Time, Value, BinOp>
+    for TimeFifo<Time, Value, BinOp, Window>
+where
+    Time: Ord + Copy,
+    BinOp: Operator,
+    Window: FifoWindow<Value, BinOp>,
+{
+    fn new() -> Self {
+        Self {
+            window: Window::new(),
+            times: ChunkedArrayQueue::new(),
+            newest: None,
+            op: PhantomData,
+        }
+    }
+    // Rejects values at times before the newest time which has been inserted,
+    // even if it has been evicted. Values at the same time are aggregated in
+    // insertion order.
+    fn insert(&mut self, t: Time, v: Value) -> Result<(), OutOfOrder<Time, Value>> {
+        match self.newest {
+            Some(newest) if t < newest => Err(OutOfOrder { time: t, value: v }),
+            _ => {
+                self.newest = Some(t);
+                let len = self.window.len();
+                self.window.push(v);
+                if self.window.len() == len {
+                    self.times.pop_front();
+                }
+                self.times.push_back(t);
+                Ok(())
+            }
+        }
+    }
+    fn evict(&mut self, t: Time) {
+        while matches!(self.oldest(), Some(oldest) if oldest < t) {
+            self.window.pop();
+            self.times.pop_front();
+        }
+    }
+    fn query(&self) -> Value {
+        self.window.query()
+    }
+    fn oldest(&self) -> Option<Time> {
+        self.oldest()
+    }
+}
+
+impl<Time, Value, BinOp, Window> TimeFifo<Time, Value, BinOp, Window>
+where
+    Time: Ord + Copy,
+    BinOp: Operator,
+    Window: FifoWindow<Value, BinOp>,
+{
+    /// Returns a window which can hold `capacity` values without allocating
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            window: Window::with_capacity(capacity),
+            times: ChunkedArrayQueue::with_capacity(capacity),
+            newest: None,
+            op: PhantomData,
+        }
+    }
+    /// Returns the time of the oldest value in the window
+    pub fn oldest(&self) -> Option<Time> {
+        self.times.front().copied()
+    }
+    /// Returns the time of the newest value in the window
+    pub fn newest(&self) -> Option<Time> {
+        self.times.back().copied()
+    }
+    pub fn len(&self) -> usize {
+        self.window.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.window.is_empty()
+    }
+}
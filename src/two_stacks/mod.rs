@@ -1,10 +1,14 @@
 use crate::FifoWindow;
 use alga::general::AbstractMonoid;
 use alga::general::Operator;
+use arrayvec::Array;
+use arrayvec::ArrayVec;
+use arrayvec::CapacityError;
 use std::marker::PhantomData;
 
+/// Value of a stack together with its partial aggregate
 #[derive(Debug)]
-struct Item<Value: Clone> {
+pub struct Item<Value: Clone> {
     agg: Value,
     val: Value,
 }
@@ -38,6 +42,13 @@ where
             op: PhantomData,
         }
     }
+    fn with_capacity(capacity: usize) -> TwoStacks<Value, BinOp> {
+        TwoStacks {
+            front: Vec::with_capacity(capacity),
+            back: Vec::with_capacity(capacity),
+            op: PhantomData,
+        }
+    }
     fn push(&mut self, v: Value) {
         self.back
             .push(Item::new(Self::agg(&self.back).operate(&v), v));
@@ -54,6 +65,9 @@ where
     fn query(&self) -> Value {
         Self::agg(&self.front).operate(&Self::agg(&self.back))
     }
+    fn len(&self) -> usize {
+        self.front.len() + self.back.len()
+    }
 }
 
 impl<T, O> TwoStacks<T, O>
@@ -62,7 +76,7 @@ where
     O: Operator,
 {
     #[inline(always)]
-    fn agg(stack: &Vec<Item<T>>) -> T {
+    fn agg(stack: &[Item<T>]) -> T {
         if let Some(top) = stack.last() {
             top.agg.clone()
         } else {
@@ -70,3 +84,93 @@ where
         }
     }
 }
+
+/// Two-Stacks whose stacks are stored inline in arrays of type `A`, e.g.,
+/// `ArrayTwoStacks<Value, BinOp, [Item<Value>; 64]>`, without allocating. The
+/// window holds at most `capacity` values, and evicts the oldest value when a
+/// value is pushed to a full window.
+#[derive(Debug)]
+pub struct ArrayTwoStacks<Value, BinOp, A>
+where
+    Value: AbstractMonoid<BinOp> + Clone,
+    BinOp: Operator,
+    A: Array<Item = Item<Value>>,
+{
+    front: ArrayVec<A>,
+    back: ArrayVec<A>,
+    capacity: usize,
+    op: PhantomData<BinOp>,
+}
+
+impl<Value, BinOp, A> FifoWindow<Value, BinOp> for ArrayTwoStacks<Value, BinOp, A>
+where
+    Value: AbstractMonoid<BinOp> + Clone,
+    BinOp: Operator,
+    A: Array<Item = Item<Value>>,
+{
+    fn new() -> ArrayTwoStacks<Value, BinOp, A> {
+        Self::with_capacity(A::CAPACITY)
+    }
+    fn with_capacity(capacity: usize) -> ArrayTwoStacks<Value, BinOp, A> {
+        assert!(
+            0 < capacity && capacity <= A::CAPACITY,
+            "capacity must be within 1..={}",
+            A::CAPACITY
+        );
+        ArrayTwoStacks {
+            front: ArrayVec::new(),
+            back: ArrayVec::new(),
+            capacity,
+            op: PhantomData,
+        }
+    }
+    fn push(&mut self, v: Value) {
+        if self.is_full() {
+            self.pop();
+        }
+        self.back
+            .push(Item::new(Self::agg(&self.back).operate(&v), v));
+    }
+    fn pop(&mut self) {
+        if self.front.is_empty() {
+            while let Some(top) = self.back.pop() {
+                self.front
+                    .push(Item::new(top.val.operate(&Self::agg(&self.front)), top.val))
+            }
+        }
+        self.front.pop();
+    }
+    fn query(&self) -> Value {
+        Self::agg(&self.front).operate(&Self::agg(&self.back))
+    }
+    fn len(&self) -> usize {
+        self.front.len() + self.back.len()
+    }
+}
+
+impl<Value, BinOp, A> ArrayTwoStacks<Value, BinOp, A>
+where
+    Value: AbstractMonoid<BinOp> + Clone,
+    BinOp: Operator,
+    A: Array<Item = Item<Value>>,
+{
+    #[inline(always)]
+    fn agg(stack: &[Item<Value>]) -> Value {
+        TwoStacks::<Value, BinOp>::agg(stack)
+    }
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity
+    }
+    /// Pushes a value, or returns it in an error if the window is full
+    pub fn try_push(&mut self, v: Value) -> Result<(), CapacityError<Value>> {
+        if self.is_full() {
+            Err(CapacityError::new(v))
+        } else {
+            self.push(v);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use crate::FifoWindow;
+use alga::general::AbstractMonoid;
+use alga::general::Operator;
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+/// Two-Stacks Lite (Tangwongsan et al., VLDBJ'21). Like Two-Stacks, but the
+/// back stores plain values and its aggregate is kept in `agg_b`. On a flip,
+/// the values of the back are overwritten in place by the partial aggregates
+/// of the front, so each item is stored once instead of twice.
+#[derive(Debug)]
+pub struct TwoStacksLite<Value, BinOp>
+where
+    Value: AbstractMonoid<BinOp> + Clone,
+    BinOp: Operator,
+{
+    // ith oldest value in FIFO order is vi
+    deque: VecDeque<Value>,
+    // Front, ∀p ∈ 0...b−1 : deque[p] = vp ⊕ ... ⊕ v(b−1)
+    // Back,  ∀p ∈ b...e−1 : deque[p] = vp
+    b: usize,
+    // vb ⊕ ... ⊕ v(e−1)
+    agg_b: Value,
+    op: PhantomData<BinOp>,
+}
+
+impl<Value, BinOp> FifoWindow<Value, BinOp> for TwoStacksLite<Value, BinOp>
+where
+    Value: AbstractMonoid<BinOp> + Clone,
+    BinOp: Operator,
+{
+    fn new() -> TwoStacksLite<Value, BinOp> {
+        TwoStacksLite {
+            deque: VecDeque::new(),
+            b: 0,
+            agg_b: Value::identity(),
+            op: PhantomData,
+        }
+    }
+    fn with_capacity(capacity: usize) -> TwoStacksLite<Value, BinOp> {
+        TwoStacksLite {
+            deque: VecDeque::with_capacity(capacity),
+            b: 0,
+            agg_b: Value::identity(),
+            op: PhantomData,
+        }
+    }
+    fn push(&mut self, v: Value) {
+        self.agg_b = self.agg_b.operate(&v);
+        self.deque.push_back(v);
+    }
+    fn pop(&mut self) {
+        if self.b == 0 {
+            self.flip();
+        }
+        if self.deque.pop_front().is_some() {
+            self.b -= 1;
+        }
+    }
+    fn query(&self) -> Value {
+        if self.b == 0 {
+            self.agg_b.clone()
+        } else {
+            self.deque[0].operate(&self.agg_b)
+        }
+    }
+    fn len(&self) -> usize {
+        self.deque.len()
+    }
+}
+
+impl<Value, BinOp> TwoStacksLite<Value, BinOp>
+where
+    Value: AbstractMonoid<BinOp> + Clone,
+    BinOp: Operator,
+{
+    // Turns the back into the front by aggregating it from the newest to the
+    // oldest value.
+    fn flip(&mut self) {
+        for p in (0..self.deque.len().saturating_sub(1)).rev() {
+            self.deque[p] = self.deque[p].operate(&self.deque[p + 1]);
+        }
+        self.b = self.deque.len();
+        self.agg_b = Value::identity();
+    }
+}
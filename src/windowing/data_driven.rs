@@ -0,0 +1,188 @@
+use crate::windowing::Emitted;
+use crate::OutOfOrder;
+use crate::TimeWindow;
+use alga::general::Operator;
+use std::marker::PhantomData;
+use std::ops::Sub;
+
+/// Where a value falls with respect to the boundaries of data-driven windows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Boundary {
+    /// The value belongs to the current window, or starts a window if none is
+    /// open
+    Within,
+    /// The value closes the current window and starts a new one
+    Starts,
+    /// The value closes the current window, and does not belong to any window
+    Closes,
+}
+
+/// Assigns values to windows whose boundaries depend on the data, rather than
+/// on time
+pub trait BoundaryAssigner<Value> {
+    /// Returns where `v` falls, given the first value of the current window,
+    /// or `None` if no window is open
+    fn boundary(&self, first: Option<&Value>, v: &Value) -> Boundary;
+}
+
+/// Delta windows, where a value starts a new window if its key differs by more
+/// than `delta` from the key of the first value of the current window
+pub struct Delta<Key, F> {
+    pub delta: Key,
+    pub key: F,
+}
+
+/// Punctuation windows, where marker values close the current window
+pub struct Punctuation<F> {
+    pub is_marker: F,
+}
+
+impl<Value, Key, F> BoundaryAssigner<Value> for Delta<Key, F>
+where
+    Key: PartialOrd + Copy + Sub<Output = Key>,
+    F: Fn(&Value) -> Key,
+{
+    fn boundary(&self, first: Option<&Value>, v: &Value) -> Boundary {
+        match first {
+            Some(first) => {
+                let (a, b) = ((self.key)(first), (self.key)(v));
+                let distance = if a < b { b - a } else { a - b };
+                if distance > self.delta {
+                    Boundary::Starts
+                } else {
+                    Boundary::Within
+                }
+            }
+            None => Boundary::Within,
+        }
+    }
+}
+
+impl<Value, F> BoundaryAssigner<Value> for Punctuation<F>
+where
+    F: Fn(&Value) -> bool,
+{
+    fn boundary(&self, _: Option<&Value>, v: &Value) -> Boundary {
+        if (self.is_marker)(v) {
+            Boundary::Closes
+        } else {
+            Boundary::Within
+        }
+    }
+}
+
+/// Emits the aggregates of data-driven windows over a time window, such as FIBA
+/// or a FIFO window in a `TimeFifo`. A window is emitted as soon as the value
+/// which closes it is inserted. Boundaries depend on the order of the values,
+/// so values must be inserted in time order.
+pub struct DataDriven<Time, Value, BinOp, Window, Assigner>
+where
+    Time: Ord + Copy,
+    BinOp: Operator,
+    Window: TimeWindow<Time, Value, BinOp>,
+    Assigner: BoundaryAssigner<Value>,
+{
+    window: Window,
+    assigner: Assigner,
+    // Time and value of the first value of the current window
+    first: Option<(Time, Value)>,
+    // Time of the newest value of the current window
+    last: Option<Time>,
+    newest: Option<Time>,
+    op: PhantomData<BinOp>,
+}
+
+impl<Time, Value, BinOp, Window, Assigner> DataDriven<Time, Value, BinOp, Window, Assigner>
+where
+    Time: Ord + Copy,
+    Value: Clone,
+    BinOp: Operator,
+    Window: TimeWindow<Time, Value, BinOp>,
+    Assigner: BoundaryAssigner<Value>,
+{
+    pub fn new(assigner: Assigner) -> Self {
+        Self {
+            window: Window::new(),
+            assigner,
+            first: None,
+            last: None,
+            newest: None,
+            op: PhantomData,
+        }
+    }
+    /// Inserts a value at time `t`, and returns the start, end, and aggregate
+    /// of the window which it closes, if any. The start of a window is the time
+    /// of its first value, and its end is the time of the value which closes
+    /// it. Returns the value in an error if `t` is before the newest time, or
+    /// if the window rejects it, in which case no window is closed.
+    pub fn insert(
+        &mut self,
+        t: Time,
+        v: Value,
+    ) -> Result<Option<Emitted<Time, Value>>, OutOfOrder<Time, Value>> {
+        if Some(t) < self.newest {
+            return Err(OutOfOrder { time: t, value: v });
+        }
+        let boundary = self
+            .assigner
+            .boundary(self.first.as_ref().map(|(_, first)| first), &v);
+        let closes = boundary != Boundary::Within && self.first.is_some();
+        if boundary == Boundary::Closes {
+            self.newest = Some(t);
+            return Ok(self.close(t));
+        }
+        // The value is inserted before the current window is closed, so that
+        // the window is kept if the value is rejected
+        let first = if closes || self.first.is_none() {
+            Some((t, v.clone()))
+        } else {
+            None
+        };
+        let result = if !closes {
+            self.window.insert(t, v)?;
+            None
+        } else if self.last == Some(t) {
+            // Values of the current window at time t are not evicted before t,
+            // so the value goes into a new window
+            let agg = self.window.query();
+            let mut window = Window::new();
+            window.insert(t, v)?;
+            self.window = window;
+            self.first.take().map(|(start, _)| (start, t, agg))
+        } else {
+            let agg = self.window.query();
+            self.window.insert(t, v)?;
+            self.window.evict(t);
+            self.first.take().map(|(start, _)| (start, t, agg))
+        };
+        if first.is_some() {
+            self.first = first;
+        }
+        self.last = Some(t);
+        self.newest = Some(t);
+        Ok(result)
+    }
+    /// Closes the current window at the newest time, e.g., at the end of the
+    /// stream, and returns its start, end, and aggregate
+    pub fn flush(&mut self) -> Option<Emitted<Time, Value>> {
+        let end = self.newest?;
+        self.close(end)
+    }
+    /// Returns the aggregate of the current window
+    pub fn query(&self) -> Value {
+        self.window.query()
+    }
+    // Closes the current window at time end, if a window is open
+    fn close(&mut self, end: Time) -> Option<Emitted<Time, Value>> {
+        let (start, _) = self.first.take()?;
+        let agg = self.window.query();
+        // Values at the end of the window are not evicted before it, so the
+        // window is only replaced if it has any
+        if self.last.take() == Some(end) {
+            self.window = Window::new();
+        } else {
+            self.window.evict(end);
+        }
+        Some((start, end, agg))
+    }
+}
@@ -0,0 +1,316 @@
+pub mod data_driven;
+pub mod session;
+pub mod slicing;
+
+use crate::OutOfOrder;
+use crate::TimeWindow;
+use crate::TimeWindowExtra;
+use alga::general::Operator;
+use num_traits::Zero;
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::ops::Add;
+use std::ops::Range;
+use std::ops::Rem;
+use std::ops::Sub;
+
+/// Start, end, and aggregate of an emitted window
+pub type Emitted<Time, Value> = (Time, Time, Value);
+
+/// Start, end, and aggregate of each emitted window
+pub type Results<Time, Value> = Vec<Emitted<Time, Value>>;
+
+/// Change to the emitted aggregate of a window, in the output mode which
+/// retracts the old aggregate of a window before its updated aggregate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change<Time, Value> {
+    /// Retracts the aggregate which was emitted for the window `start..end`
+    Retract(Time, Time, Value),
+    /// Emits an aggregate for the window `start..end`
+    Insert(Time, Time, Value),
+}
+
+/// Assigns values to windows `start..end`. Windows are produced in order of
+/// their end, and the starts of the windows must never decrease.
+pub trait WindowAssigner<Time> {
+    /// Returns the oldest window which ends after `t`, so that every window
+    /// before it ends at or before `t`
+    fn first(&self, t: Time) -> Range<Time>;
+    /// Returns the window which follows `window`
+    fn next(&self, window: &Range<Time>) -> Range<Time>;
+}
+
+/// Non-overlapping windows of a fixed size which start at multiples of the
+/// size from time zero, e.g., every 10 seconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tumbling<Time> {
+    pub size: Time,
+}
+
+/// Overlapping windows of a fixed size which start at multiples of `slide`
+/// from time zero, e.g., of 10 seconds every 2 seconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sliding<Time> {
+    pub size: Time,
+    pub slide: Time,
+}
+
+/// Windows which all start at time zero and end every `slide` time units,
+/// i.e., the windows grow forever and no values are evicted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Landmark<Time> {
+    pub slide: Time,
+}
+
+// Returns the newest multiple of `step` at or before t, where t is not
+// negative
+fn align<Time>(t: Time, step: Time) -> Time
+where
+    Time: Copy + Sub<Output = Time> + Rem<Output = Time>,
+{
+    t - t % step
+}
+
+impl<Time> WindowAssigner<Time> for Tumbling<Time>
+where
+    Time: Ord + Copy + Add<Output = Time> + Sub<Output = Time> + Rem<Output = Time> + Zero,
+{
+    // Windows start at time zero at the earliest
+    fn first(&self, t: Time) -> Range<Time> {
+        assert!(Time::zero() < self.size, "Window size must be positive");
+        let start = if t < self.size {
+            Time::zero()
+        } else {
+            align(t, self.size)
+        };
+        start..start + self.size
+    }
+    fn next(&self, window: &Range<Time>) -> Range<Time> {
+        window.end..window.end + self.size
+    }
+}
+
+impl<Time> WindowAssigner<Time> for Sliding<Time>
+where
+    Time: Ord + Copy + Add<Output = Time> + Sub<Output = Time> + Rem<Output = Time> + Zero,
+{
+    // The window after the newest one which starts at or before t - size
+    fn first(&self, t: Time) -> Range<Time> {
+        assert!(Time::zero() < self.size, "Window size must be positive");
+        assert!(Time::zero() < self.slide, "Window slide must be positive");
+        let start = if t < self.size {
+            Time::zero()
+        } else {
+            align(t - self.size, self.slide) + self.slide
+        };
+        start..start + self.size
+    }
+    fn next(&self, window: &Range<Time>) -> Range<Time> {
+        window.start + self.slide..window.end + self.slide
+    }
+}
+
+impl<Time> WindowAssigner<Time> for Landmark<Time>
+where
+    Time: Ord + Copy + Add<Output = Time> + Sub<Output = Time> + Rem<Output = Time> + Zero,
+{
+    fn first(&self, t: Time) -> Range<Time> {
+        assert!(Time::zero() < self.slide, "Window slide must be positive");
+        let end = if t < self.slide {
+            self.slide
+        } else {
+            align(t, self.slide) + self.slide
+        };
+        Time::zero()..end
+    }
+    fn next(&self, window: &Range<Time>) -> Range<Time> {
+        window.start..window.end + self.slide
+    }
+}
+
+/// Emits the aggregates of the windows of an assigner over a time window, such
+/// as FIBA, which may receive values out of order. A window is emitted once the
+/// watermark passes its end, by a range query. Windows without values are not
+/// emitted, and the driver jumps over them to the window of the next value, so
+/// that gaps in the stream cost O(1) rather than a query per window.
+///
+/// With an allowed lateness, a window which has been emitted is kept until the
+/// watermark passes its end plus the lateness. A late value which belongs to
+/// such a window updates it, and the updated aggregate is emitted again. Values
+/// older than all windows which are kept are too late; they are returned in an
+/// error so that they can be dropped or sent to a side output, and evicted
+/// automatically as the watermark advances. The aggregates are emitted either
+/// as plain results, or as changes which retract the previous aggregate of a
+/// window before inserting its update. Either way, an update costs one range
+/// query.
+pub struct Driver<Time, Value, BinOp, Window, Assigner>
+where
+    Time: Ord + Copy,
+    BinOp: Operator,
+    Window: TimeWindow<Time, Value, BinOp> + TimeWindowExtra<Time, Value, BinOp>,
+    Assigner: WindowAssigner<Time>,
+{
+    window: Window,
+    assigner: Assigner,
+    // Oldest window which has not been emitted
+    next: Range<Time>,
+    // Windows which have been passed by the watermark but may still be
+    // updated, in order of end, with their last emitted aggregates, or None if
+    // they had no values
+    emitted: VecDeque<(Range<Time>, Option<Value>)>,
+    lateness: Time,
+    watermark: Option<Time>,
+    // Number of values which were inserted after the watermark passed them,
+    // and number of values which were too late to be inserted
+    late: usize,
+    dropped: usize,
+    op: PhantomData<(Value, BinOp)>,
+}
+
+impl<Time, Value, BinOp, Window, Assigner> Driver<Time, Value, BinOp, Window, Assigner>
+where
+    Time: Ord + Copy + Add<Output = Time> + Sub<Output = Time> + Zero,
+    Value: Clone,
+    BinOp: Operator,
+    Window: TimeWindow<Time, Value, BinOp> + TimeWindowExtra<Time, Value, BinOp>,
+    Assigner: WindowAssigner<Time>,
+{
+    pub fn new(assigner: Assigner) -> Self {
+        Self::with_lateness(assigner, Time::zero())
+    }
+    /// Creates a driver whose windows are updated by late values which arrive
+    /// before the watermark passes the end of the window plus `lateness`
+    pub fn with_lateness(assigner: Assigner, lateness: Time) -> Self {
+        Self {
+            window: Window::new(),
+            next: assigner.first(Time::zero()),
+            assigner,
+            emitted: VecDeque::new(),
+            lateness,
+            watermark: None,
+            late: 0,
+            dropped: 0,
+            op: PhantomData,
+        }
+    }
+    /// Inserts a value at time `t`, and returns the start, end, and updated
+    /// aggregate of each emitted window which it is late for. Returns the value
+    /// in an error if `t` is before the start of all windows which may still be
+    /// updated or emitted, or if the time window rejects it. Either way, the
+    /// value counts as dropped.
+    pub fn insert(
+        &mut self,
+        t: Time,
+        v: Value,
+    ) -> Result<Results<Time, Value>, OutOfOrder<Time, Value>> {
+        let changes = self.insert_changes(t, v)?;
+        Ok(changes
+            .into_iter()
+            .filter_map(|change| match change {
+                Change::Retract(..) => None,
+                Change::Insert(start, end, agg) => Some((start, end, agg)),
+            })
+            .collect())
+    }
+    /// Inserts a value at time `t` as `insert` does, and returns a retraction
+    /// of the old aggregate followed by the updated aggregate of each emitted
+    /// window which it is late for. A window which had no values when the
+    /// watermark passed it has no old aggregate to retract.
+    pub fn insert_changes(
+        &mut self,
+        t: Time,
+        v: Value,
+    ) -> Result<Vec<Change<Time, Value>>, OutOfOrder<Time, Value>> {
+        if t < self.oldest() {
+            self.dropped += 1;
+            return Err(OutOfOrder { time: t, value: v });
+        }
+        if let Err(error) = self.window.insert(t, v) {
+            self.dropped += 1;
+            return Err(error);
+        }
+        if Some(t) < self.watermark {
+            self.late += 1;
+        }
+        let mut changes = Vec::new();
+        for (emitted, agg) in self.emitted.iter_mut() {
+            if emitted.start <= t && t < emitted.end {
+                let new = self.window.range_query(emitted.clone());
+                if let Some(old) = agg.replace(new.clone()) {
+                    changes.push(Change::Retract(emitted.start, emitted.end, old));
+                }
+                changes.push(Change::Insert(emitted.start, emitted.end, new));
+            }
+        }
+        Ok(changes)
+    }
+    /// Advances the watermark to `watermark`, and returns the start, end, and
+    /// aggregate of each window with values which ends at or before it. Values
+    /// which are too late for all windows are evicted.
+    pub fn advance(&mut self, watermark: Time) -> Results<Time, Value> {
+        let mut results = Vec::new();
+        self.watermark = self.watermark.max(Some(watermark));
+        let lateness = self.lateness;
+        while matches!(self.emitted.front(), Some((w, _)) if w.end + lateness <= watermark) {
+            self.emitted.pop_front();
+        }
+        while self.next.end <= watermark {
+            let oldest = self.window.oldest_since(self.next.start);
+            // Windows are only kept while they may be updated
+            if matches!(oldest, Some(t) if t < self.next.end) {
+                let agg = self.window.range_query(self.next.clone());
+                if watermark < self.next.end + lateness {
+                    self.emitted
+                        .push_back((self.next.clone(), Some(agg.clone())));
+                }
+                results.push((self.next.start, self.next.end, agg));
+            } else if watermark < self.next.end + lateness {
+                self.emitted.push_back((self.next.clone(), None));
+            } else {
+                // Every window which ends at or before both the next value and
+                // the watermark minus the lateness has no values and can no
+                // longer be updated
+                let horizon = watermark - lateness;
+                let t = oldest.map_or(horizon, |t| t.min(horizon));
+                self.next = self.assigner.first(t);
+                continue;
+            }
+            self.next = self.assigner.next(&self.next);
+        }
+        self.window.evict(self.oldest());
+        results
+    }
+    /// Advances the watermark as `advance` does, and returns the aggregate of
+    /// each window which ends at or before it as an insertion
+    pub fn advance_changes(&mut self, watermark: Time) -> Vec<Change<Time, Value>> {
+        self.advance(watermark)
+            .into_iter()
+            .map(|(start, end, agg)| Change::Insert(start, end, agg))
+            .collect()
+    }
+    /// Returns the watermark, if it has been advanced
+    pub fn watermark(&self) -> Option<Time> {
+        self.watermark
+    }
+    /// Returns the oldest window which has not been emitted
+    pub fn next_window(&self) -> Range<Time> {
+        self.next.clone()
+    }
+    /// Returns the number of values which were inserted after the watermark
+    /// passed them
+    pub fn late(&self) -> usize {
+        self.late
+    }
+    /// Returns the number of values which were too late to be inserted
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+    // Returns the start of the oldest window which may still be updated or
+    // emitted. Windows are emitted in order of end, and their starts never
+    // decrease.
+    fn oldest(&self) -> Time {
+        self.emitted
+            .front()
+            .map_or(self.next.start, |(emitted, _)| emitted.start)
+    }
+}
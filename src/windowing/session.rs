@@ -0,0 +1,115 @@
+use crate::OutOfOrder;
+use crate::TimeWindow;
+use crate::TimeWindowExtra;
+use alga::general::Operator;
+use num_traits::Zero;
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use std::ops::Add;
+
+/// Session windows over a time window, such as FIBA, which may receive values
+/// out of order. A session is a maximal sequence of values where each value is
+/// at most `gap` time units after the previous one, i.e., a session closes
+/// after a gap longer than `gap` without values. A late value which bridges the
+/// gap between two sessions merges them. A session whose last value is at time
+/// `last` is emitted as the window `start..last + gap` once the watermark
+/// passes `last + gap`. The gap must be positive, so that the window contains
+/// `last`.
+pub struct Sessions<Time, Value, BinOp, Window>
+where
+    Time: Ord + Copy + Add<Output = Time> + Zero,
+    BinOp: Operator,
+    Window: TimeWindow<Time, Value, BinOp> + TimeWindowExtra<Time, Value, BinOp>,
+{
+    window: Window,
+    gap: Time,
+    // Start and time of the last value of each session which has not been
+    // emitted, indexed by start
+    sessions: BTreeMap<Time, Time>,
+    watermark: Option<Time>,
+    op: PhantomData<(Value, BinOp)>,
+}
+
+impl<Time, Value, BinOp, Window> Sessions<Time, Value, BinOp, Window>
+where
+    Time: Ord + Copy + Add<Output = Time> + Zero,
+    BinOp: Operator,
+    Window: TimeWindow<Time, Value, BinOp> + TimeWindowExtra<Time, Value, BinOp>,
+{
+    pub fn new(gap: Time) -> Self {
+        assert!(Time::zero() < gap, "Session gap must be positive");
+        Self {
+            window: Window::new(),
+            gap,
+            sessions: BTreeMap::new(),
+            watermark: None,
+            op: PhantomData,
+        }
+    }
+    /// Inserts a value at time `t`, or returns it in an error if `t` is before
+    /// the watermark
+    pub fn insert(&mut self, t: Time, v: Value) -> Result<(), OutOfOrder<Time, Value>> {
+        if Some(t) < self.watermark {
+            return Err(OutOfOrder { time: t, value: v });
+        }
+        self.window.insert(t, v)?;
+        // Sessions which start at or before t, and after t
+        let before = self
+            .sessions
+            .range(..=t)
+            .next_back()
+            .map(|(start, last)| (*start, *last))
+            .filter(|(_, last)| t <= *last + self.gap);
+        let after = self
+            .sessions
+            .range(t..)
+            .find(|(start, _)| t < **start)
+            .map(|(start, last)| (*start, *last))
+            .filter(|(start, _)| *start <= t + self.gap);
+        match (before, after) {
+            (Some((start, last)), Some((next_start, next_last))) => {
+                self.sessions.remove(&next_start);
+                self.sessions.insert(start, last.max(next_last));
+            }
+            (Some((start, last)), None) => {
+                self.sessions.insert(start, last.max(t));
+            }
+            (None, Some((next_start, next_last))) => {
+                self.sessions.remove(&next_start);
+                self.sessions.insert(t, next_last);
+            }
+            (None, None) => {
+                self.sessions.insert(t, t);
+            }
+        }
+        Ok(())
+    }
+    /// Advances the watermark to `watermark`, and returns the start, end, and
+    /// aggregate of each session which it closes, in time order
+    pub fn advance(&mut self, watermark: Time) -> Vec<(Time, Time, Value)> {
+        let mut results = Vec::new();
+        self.watermark = self.watermark.max(Some(watermark));
+        while let Some((&start, &last)) = self.sessions.iter().next() {
+            let end = last + self.gap;
+            if watermark <= end {
+                break;
+            }
+            self.sessions.remove(&start);
+            results.push((start, end, self.window.range_query(start..end)));
+        }
+        // All values belong to a session, so the values before the oldest
+        // session have been emitted
+        let oldest = self.sessions.keys().next().copied();
+        self.window.evict(oldest.unwrap_or(watermark));
+        results
+    }
+    /// Returns the start and time of the last value of each session which has
+    /// not been emitted, in time order
+    pub fn sessions(&self) -> impl Iterator<Item = (Time, Time)> + '_ {
+        self.sessions.iter().map(|(start, last)| (*start, *last))
+    }
+    /// Returns the watermark, if it has been advanced
+    pub fn watermark(&self) -> Option<Time> {
+        self.watermark
+    }
+}
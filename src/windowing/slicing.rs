@@ -0,0 +1,358 @@
+use crate::fiba::FIBA;
+use crate::windowing::WindowAssigner;
+use crate::OutOfOrder;
+use crate::QueryId;
+use crate::TimeWindow;
+use crate::TimeWindowExtra;
+use alga::general::AbstractMonoid;
+use alga::general::Operator;
+use num_traits::Zero;
+use std::collections::BTreeMap;
+use std::ops::Add;
+use std::ops::Range;
+
+/// General stream slicing, from [1] in the README. Values are aggregated into
+/// slices whose edges are the union of the starts and ends of the windows of
+/// all queries, and the partial aggregates of the slices are stored in a FIBA
+/// which is shared by all queries. The aggregate of a window is a range query
+/// over its slices. Queries are either windows of an assigner, e.g., tumbling
+/// or sliding windows, or session windows whose edges depend on the data.
+///
+/// Values of the same slice are aggregated in the order they arrive, so the
+/// operator must be commutative if values arrive out of order.
+///
+/// The edges of the windows of an assigner are added from the window of the
+/// first value, or the oldest window which ends after the watermark if it was
+/// advanced before the first value, and back to the windows of older values
+/// when they arrive. Windows without values are not emitted, and are skipped
+/// without adding their edges or querying them, so that gaps in the stream
+/// cost O(1) per query.
+pub struct Slicing<Time, Value, BinOp>
+where
+    Time: Ord + Copy + Add<Output = Time> + Zero + 'static,
+    Value: Clone + AbstractMonoid<BinOp> + 'static,
+    BinOp: Operator + 'static,
+{
+    // Partial aggregate of each non-empty slice, indexed by the start of the slice
+    slices: FIBA<Time, Value, BinOp>,
+    // Edges of the slices, with the number of window boundaries at each edge
+    edges: BTreeMap<Time, usize>,
+    queries: Vec<Query<Time>>,
+    newest: Option<Time>,
+    watermark: Option<Time>,
+}
+
+enum Query<Time> {
+    Assigned {
+        assigner: Box<dyn WindowAssigner<Time>>,
+        // Newest window whose edges have been added
+        added: Option<Range<Time>>,
+        // Time after which the edges of all windows up to `added` have been
+        // added, once a value has arrived. It is at or before all values.
+        from: Option<Time>,
+        // Oldest window which has not been emitted, once a value has arrived
+        next: Option<Range<Time>>,
+    },
+    Sessions {
+        gap: Time,
+        // Start and time of the last value of each session which has not been
+        // emitted, indexed by start
+        sessions: BTreeMap<Time, Time>,
+    },
+}
+
+impl<Time, Value, BinOp> Slicing<Time, Value, BinOp>
+where
+    Time: Ord + Copy + Add<Output = Time> + Zero + 'static,
+    Value: Clone + AbstractMonoid<BinOp> + 'static,
+    BinOp: Operator + 'static,
+{
+    pub fn new() -> Self {
+        Self {
+            slices: FIBA::new(),
+            edges: BTreeMap::new(),
+            queries: Vec::new(),
+            newest: None,
+            watermark: None,
+        }
+    }
+    /// Registers a query over the windows of an assigner. Queries must be
+    /// registered before values are inserted.
+    pub fn add_query(&mut self, assigner: impl WindowAssigner<Time> + 'static) -> QueryId {
+        assert!(
+            self.newest.is_none(),
+            "Queries must be registered before values are inserted"
+        );
+        self.queries.push(Query::Assigned {
+            assigner: Box::new(assigner),
+            added: None,
+            from: None,
+            next: None,
+        });
+        self.queries.len() - 1
+    }
+    /// Registers a query over session windows with a gap of `gap`, as in
+    /// `Sessions`. Queries must be registered before values are inserted.
+    pub fn add_session_query(&mut self, gap: Time) -> QueryId {
+        assert!(Time::zero() < gap, "Session gap must be positive");
+        assert!(
+            self.newest.is_none(),
+            "Queries must be registered before values are inserted"
+        );
+        self.queries.push(Query::Sessions {
+            gap,
+            sessions: BTreeMap::new(),
+        });
+        self.queries.len() - 1
+    }
+    /// Inserts a value at time `t` into its slice, or returns it in an error if
+    /// `t` is before the watermark
+    pub fn insert(&mut self, t: Time, v: Value) -> Result<(), OutOfOrder<Time, Value>> {
+        if Some(t) < self.watermark {
+            return Err(OutOfOrder { time: t, value: v });
+        }
+        self.newest = self.newest.max(Some(t));
+        for id in 0..self.queries.len() {
+            self.add_edges(id, t);
+        }
+        // Values before all edges do not belong to any window
+        if let Some((&start, _)) = self.edges.range(..=t).next_back() {
+            self.slices.insert(start, v);
+        }
+        Ok(())
+    }
+    /// Advances the watermark to `watermark`, and returns the query id, start,
+    /// end, and aggregate of each window with values which it closes
+    pub fn advance(&mut self, watermark: Time) -> Vec<(QueryId, Time, Time, Value)> {
+        let mut results = Vec::new();
+        self.watermark = self.watermark.max(Some(watermark));
+        let slices = &mut self.slices;
+        for (id, query) in self.queries.iter_mut().enumerate() {
+            match query {
+                Query::Assigned {
+                    assigner,
+                    next: Some(next),
+                    ..
+                } => {
+                    while next.end <= watermark {
+                        // Slices start at the edges of the window, so the
+                        // window has values if a slice starts within it.
+                        // Otherwise, the windows before the oldest slice after
+                        // it have no values either.
+                        match slices.oldest_since(next.start) {
+                            Some(t) if t < next.end => {
+                                let agg = TimeWindowExtra::range_query(slices, next.clone());
+                                results.push((id, next.start, next.end, agg));
+                                *next = assigner.next(next);
+                            }
+                            oldest => {
+                                *next =
+                                    assigner.first(oldest.map_or(watermark, |t| t.min(watermark)))
+                            }
+                        }
+                    }
+                }
+                Query::Assigned { next: None, .. } => {}
+                Query::Sessions { gap, sessions } => {
+                    while let Some((&start, &last)) = sessions.iter().next() {
+                        let end = last + *gap;
+                        if watermark <= end {
+                            break;
+                        }
+                        sessions.remove(&start);
+                        let agg = TimeWindowExtra::range_query(slices, start..end);
+                        results.push((id, start, end, agg));
+                    }
+                }
+            }
+        }
+        self.evict(watermark);
+        results
+    }
+    /// Returns the edges of the slices which have not been evicted
+    pub fn edges(&self) -> impl Iterator<Item = Time> + '_ {
+        self.edges.keys().copied()
+    }
+    /// Returns the watermark, if it has been advanced
+    pub fn watermark(&self) -> Option<Time> {
+        self.watermark
+    }
+    // Adds the edges which a value at time t introduces to a query
+    fn add_edges(&mut self, id: QueryId, t: Time) {
+        let mut new_edges = Vec::new();
+        let mut old_edges = Vec::new();
+        let mut older_edges = Vec::new();
+        let watermark = self.watermark;
+        match &mut self.queries[id] {
+            Query::Assigned {
+                assigner,
+                added,
+                from,
+                next,
+            } => {
+                match *from {
+                    None => {
+                        let time = watermark.unwrap_or(t);
+                        *from = Some(time);
+                        *next = Some(assigner.first(time));
+                    }
+                    // The windows which end after t and at or before `from`
+                    // have not been added
+                    Some(time) if t < time => {
+                        let mut window = assigner.first(t);
+                        if !matches!(next, Some(next) if next.end <= window.end) {
+                            *next = Some(window.clone());
+                        }
+                        while window.end <= time {
+                            older_edges.push(window.start);
+                            older_edges.push(window.end);
+                            window = assigner.next(&window);
+                        }
+                        *from = Some(t);
+                    }
+                    Some(_) => {}
+                }
+                // Windows are added until they start after t. Windows with the
+                // same start as the previous window, e.g., landmark windows,
+                // are added until the previous window ends after t. Other
+                // windows which have not been added start after all values, so
+                // those which end at or before the watermark never have values
+                // and are skipped.
+                loop {
+                    let window = match added {
+                        Some(previous) => {
+                            let window = assigner.next(previous);
+                            match watermark {
+                                Some(watermark)
+                                    if previous.start < window.start && window.end <= watermark =>
+                                {
+                                    assigner.first(watermark)
+                                }
+                                _ => window,
+                            }
+                        }
+                        None => assigner.first(from.unwrap()),
+                    };
+                    let needed = match added {
+                        Some(previous) if previous.start == window.start => previous.end <= t,
+                        _ => window.start <= t,
+                    };
+                    if !needed {
+                        break;
+                    }
+                    new_edges.push(window.start);
+                    new_edges.push(window.end);
+                    *added = Some(window);
+                }
+            }
+            Query::Sessions { gap, sessions } => {
+                let gap = *gap;
+                let before = sessions
+                    .range(..=t)
+                    .next_back()
+                    .map(|(start, last)| (*start, *last))
+                    .filter(|(_, last)| t <= *last + gap);
+                let after = sessions
+                    .range(t..)
+                    .find(|(start, _)| t < **start)
+                    .map(|(start, last)| (*start, *last))
+                    .filter(|(start, _)| *start <= t + gap);
+                match (before, after) {
+                    (Some((start, last)), Some((next_start, next_last))) => {
+                        sessions.remove(&next_start);
+                        sessions.insert(start, next_last);
+                        old_edges.push(last + gap);
+                        old_edges.push(next_start);
+                    }
+                    (Some((start, last)), None) => {
+                        if last < t {
+                            sessions.insert(start, t);
+                            new_edges.push(t + gap);
+                            old_edges.push(last + gap);
+                        }
+                    }
+                    (None, Some((next_start, next_last))) => {
+                        sessions.remove(&next_start);
+                        sessions.insert(t, next_last);
+                        new_edges.push(t);
+                        old_edges.push(next_start);
+                    }
+                    (None, None) => {
+                        sessions.insert(t, t);
+                        new_edges.push(t);
+                        new_edges.push(t + gap);
+                    }
+                }
+            }
+        }
+        // Edges of windows after `from` are after all previous values, and
+        // sessions contain all values so there are no values between the end
+        // of a session and the start of the next one. Hence, adding these
+        // edges never splits a slice with values. Edges are added before they are
+        // removed, so that slices are merged into the right slice.
+        for t in new_edges {
+            *self.edges.entry(t).or_insert(0) += 1;
+        }
+        for t in old_edges {
+            self.remove_edge(t);
+        }
+        // Edges of windows older than `from` are at or before all values, so
+        // the values of the slice which a new edge splits belong after it
+        older_edges.sort();
+        for t in older_edges {
+            let count = self.edges.entry(t).or_insert(0);
+            *count += 1;
+            if *count == 1 {
+                if let Some((&start, _)) = self.edges.range(..t).next_back() {
+                    if let Some(v) = self.slices.remove(start) {
+                        self.slices.insert(t, v);
+                    }
+                }
+            }
+        }
+    }
+    // Removes a window boundary at t. If no other window has a boundary at t,
+    // the slice which starts at t is merged into the previous slice.
+    fn remove_edge(&mut self, t: Time) {
+        let count = self.edges.get_mut(&t).unwrap();
+        *count -= 1;
+        if *count == 0 {
+            self.edges.remove(&t);
+            if let Some(v) = self.slices.remove(t) {
+                if let Some((&start, _)) = self.edges.range(..t).next_back() {
+                    self.slices.insert(start, v);
+                }
+            }
+        }
+    }
+    // Evicts the slices before the oldest window which has not been emitted
+    fn evict(&mut self, watermark: Time) {
+        let oldest = self
+            .queries
+            .iter()
+            .map(|query| match query {
+                Query::Assigned { next, .. } => next.as_ref().map_or(watermark, |next| next.start),
+                Query::Sessions { sessions, .. } => {
+                    sessions.keys().next().copied().unwrap_or(watermark)
+                }
+            })
+            .min();
+        // The slice which contains the oldest time must be kept
+        let oldest = oldest.and_then(|oldest| self.edges.range(..=oldest).next_back());
+        if let Some((&oldest, _)) = oldest {
+            TimeWindow::evict(&mut self.slices, oldest);
+            self.edges = self.edges.split_off(&oldest);
+        }
+    }
+}
+
+impl<Time, Value, BinOp> Default for Slicing<Time, Value, BinOp>
+where
+    Time: Ord + Copy + Add<Output = Time> + Zero + 'static,
+    Value: Clone + AbstractMonoid<BinOp> + 'static,
+    BinOp: Operator + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
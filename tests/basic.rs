@@ -105,13 +105,13 @@ mod fiba {
     #[test]
     fn fiba_big_query() {
         let mut tree: FIBA<Time, Value, BinOp> = FIBA::new();
-        let timestamps = (2 as i64).pow(22);
+        let timestamps = 2_i64.pow(22);
         for t in 0..timestamps {
             tree.insert(Time(t), Value(1));
         }
         for _ in 0..10 {
             for exponent in 0..22 {
-                let d = (2 as i64).pow(exponent);
+                let d = 2_i64.pow(exponent);
                 tree.insert(Time(d), Value(0));
                 tree.evict(Time(d));
                 tree.query();
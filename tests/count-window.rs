@@ -0,0 +1,109 @@
+use alga::general::AbstractMagma;
+use alga::general::AbstractMonoid;
+use alga::general::AbstractSemigroup;
+use alga::general::Identity;
+use alga::general::Operator;
+use swag::count_window::*;
+use swag::daba::queue::Deque;
+use swag::daba::*;
+use swag::daba_lite::*;
+use swag::flat_fat::ra_fifo::*;
+use swag::ioa::*;
+use swag::rfs::*;
+use swag::two_stacks::*;
+use swag::two_stacks_lite::*;
+use swag::*;
+
+// Concatenation, which is not commutative
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Value(Vec<i32>);
+
+#[derive(Copy, Clone, Debug)]
+struct Concat;
+
+impl Operator for Concat {
+    fn operator_token() -> Concat {
+        Concat
+    }
+}
+
+impl Identity<Concat> for Value {
+    fn identity() -> Value {
+        Value(Vec::new())
+    }
+}
+
+impl AbstractMagma<Concat> for Value {
+    fn operate(&self, other: &Self) -> Self {
+        Value(self.0.iter().chain(other.0.iter()).cloned().collect())
+    }
+}
+
+impl AbstractSemigroup<Concat> for Value {}
+impl AbstractMonoid<Concat> for Value {}
+
+fn test_count_window<Window>()
+where
+    Window: FifoWindow<Value, Concat>,
+{
+    for size in 1..20 {
+        let mut window = CountWindow::<Value, Concat, Window>::new(size);
+        assert!(window.is_empty());
+        for i in 0..100 {
+            let agg = window.push(Value(vec![i]));
+            let expected = (0.max(i + 1 - size as i32)..=i).collect::<Vec<_>>();
+            assert_eq!(agg, Value(expected));
+            assert_eq!(window.len(), size.min(i as usize + 1));
+        }
+    }
+}
+
+#[test]
+fn test_count_window_rfs() {
+    test_count_window::<RFS<Value, Concat>>();
+}
+
+#[test]
+fn test_count_window_two_stacks() {
+    test_count_window::<TwoStacks<Value, Concat>>();
+}
+
+#[test]
+fn test_count_window_two_stacks_lite() {
+    test_count_window::<TwoStacksLite<Value, Concat>>();
+}
+
+#[test]
+fn test_count_window_array_two_stacks() {
+    test_count_window::<ArrayTwoStacks<Value, Concat, [_; 32]>>();
+}
+
+#[test]
+fn test_count_window_daba() {
+    test_count_window::<DABA<Value, Concat>>();
+}
+
+#[test]
+fn test_count_window_daba_deque() {
+    test_count_window::<DABA<Value, Concat, Deque<_>>>();
+}
+
+#[test]
+fn test_count_window_array_daba() {
+    test_count_window::<ArrayDABA<Value, Concat, [_; 32]>>();
+}
+
+#[test]
+fn test_count_window_daba_lite() {
+    test_count_window::<DABALite<Value, Concat>>();
+}
+
+#[test]
+fn test_count_window_ioa() {
+    test_count_window::<IOA<Value, Concat>>();
+}
+
+#[test]
+fn test_count_window_flat_fat() {
+    test_count_window::<RA<Value, Concat>>();
+}
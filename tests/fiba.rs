@@ -0,0 +1,167 @@
+use alga::general::AbstractMagma;
+use alga::general::AbstractMonoid;
+use alga::general::AbstractSemigroup;
+use alga::general::Identity;
+use alga::general::Operator;
+use swag::fiba::*;
+use swag::TimeWindowExtra;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rand::SeedableRng;
+use std::collections::BTreeMap;
+use std::ops::RangeBounds;
+
+// Concatenation, which is not commutative
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Value(Vec<i32>);
+
+#[derive(Copy, Clone, Debug)]
+struct Concat;
+
+impl Operator for Concat {
+    fn operator_token() -> Concat {
+        Concat
+    }
+}
+
+impl Identity<Concat> for Value {
+    fn identity() -> Value {
+        Value(Vec::new())
+    }
+}
+
+impl AbstractMagma<Concat> for Value {
+    fn operate(&self, other: &Self) -> Self {
+        Value(self.0.iter().chain(other.0.iter()).cloned().collect())
+    }
+}
+
+impl AbstractSemigroup<Concat> for Value {}
+impl AbstractMonoid<Concat> for Value {}
+
+type Time = i64;
+
+// FIBA together with a naive window, where values at the same time are
+// aggregated in insertion order
+struct Differential {
+    fiba: FIBA<Time, Value, Concat>,
+    naive: BTreeMap<Time, Vec<i32>>,
+}
+
+impl Differential {
+    fn new() -> Self {
+        Self {
+            fiba: FIBA::new(),
+            naive: BTreeMap::new(),
+        }
+    }
+    fn insert(&mut self, t: Time, v: i32) {
+        self.fiba.insert(t, Value(vec![v]));
+        self.naive.entry(t).or_default().push(v);
+    }
+    fn evict(&mut self, t: Time) {
+        self.fiba.evict(t);
+        self.naive.remove(&t);
+    }
+    fn expected(&self, range: impl RangeBounds<Time>) -> Value {
+        Value(
+            self.naive
+                .range(range)
+                .flat_map(|(_, values)| values.iter().cloned())
+                .collect(),
+        )
+    }
+    fn check(&self) {
+        let expected = self.expected(..);
+        assert_eq!(self.fiba.query(), expected);
+    }
+}
+
+// Out-of-order inserts search for their node from the fingers, which must not
+// stop at an ancestor of the finger which does not encompass the time
+#[test]
+fn test_out_of_order_inserts() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut window = Differential::new();
+    for i in 0..2000 {
+        window.insert(rng.gen_range(0, 1000), i);
+        window.check();
+    }
+}
+
+// Inserts which split nodes on a spine must repair the aggregates of the
+// spine from where repairing up stopped, including the node where rebalancing
+// topped out
+#[test]
+fn test_inserts_at_both_ends() {
+    let mut window = Differential::new();
+    for i in 0..500 {
+        window.insert(1000 + i as Time, i);
+        window.check();
+        window.insert(1000 - i as Time, -i);
+        window.check();
+    }
+}
+
+// Evictions in random order steal items from siblings through the item which
+// separates them in the parent, merge nodes whose aggregates must be repaired,
+// and may free the node which held the evicted item
+#[test]
+fn test_random_evictions() {
+    let mut rng = StdRng::seed_from_u64(0);
+    for &n in [10, 100, 1000].iter() {
+        let mut window = Differential::new();
+        let mut times = (0..n).collect::<Vec<Time>>();
+        times.shuffle(&mut rng);
+        for (i, &t) in times.iter().enumerate() {
+            window.insert(t, i as i32);
+        }
+        window.check();
+        times.shuffle(&mut rng);
+        for &t in times.iter() {
+            window.evict(t);
+            window.check();
+        }
+    }
+}
+
+// Random inserts and evictions of inner items, where an evicted item is
+// replaced by its neighbour from a leaf
+#[test]
+fn test_random_inserts_and_evictions() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut window = Differential::new();
+    for i in 0..5000 {
+        if rng.gen_bool(0.6) || window.naive.is_empty() {
+            window.insert(rng.gen_range(0, 500), i);
+        } else {
+            let times = window.naive.keys().cloned().collect::<Vec<_>>();
+            window.evict(*times.choose(&mut rng).unwrap());
+        }
+        window.check();
+    }
+}
+
+// Range queries start at the least common ancestor of the fingers, whose
+// subtree must encompass the whole range
+#[test]
+fn test_random_range_queries() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut window = Differential::new();
+    for i in 0..1000 {
+        window.insert(rng.gen_range(0, 1000), i);
+        let start = rng.gen_range(0, 1000);
+        let end = start + rng.gen_range(0, 200);
+        // The inherent range query includes the end of the range
+        assert_eq!(
+            window.fiba.range_query(start..end),
+            window.expected(start..=end)
+        );
+        assert_eq!(
+            TimeWindowExtra::range_query(&mut window.fiba, start..end),
+            window.expected(start..end)
+        );
+    }
+}
@@ -7,13 +7,22 @@ use alga::general::AbstractSemigroup;
 use alga::general::Identity;
 use alga::general::Operator;
 use alga::general::TwoSidedInverse;
+use swag::daba::queue::Deque;
 use swag::daba::*;
+use swag::daba_lite::*;
+use swag::flat_fat::ra_fifo::*;
+use swag::ioa::*;
 use swag::rfs::*;
 use swag::soe::*;
 use swag::two_stacks::*;
-use swag::flat_fat::ra_fifo::*;
+use swag::two_stacks_lite::*;
 use swag::*;
 
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+use std::collections::VecDeque;
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 struct Value(i32);
 
@@ -50,6 +59,34 @@ impl AbstractQuasigroup<Sum> for Value {}
 impl AbstractLoop<Sum> for Value {}
 impl AbstractGroup<Sum> for Value {}
 
+// Concatenation, which is not commutative
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Word(Vec<i32>);
+
+#[derive(Copy, Clone, Debug)]
+struct Concat;
+
+impl Operator for Concat {
+    fn operator_token() -> Concat {
+        Concat
+    }
+}
+
+impl Identity<Concat> for Word {
+    fn identity() -> Word {
+        Word(Vec::new())
+    }
+}
+
+impl AbstractMagma<Concat> for Word {
+    fn operate(&self, other: &Self) -> Self {
+        Word(self.0.iter().chain(other.0.iter()).cloned().collect())
+    }
+}
+
+impl AbstractSemigroup<Concat> for Word {}
+impl AbstractMonoid<Concat> for Word {}
+
 fn test_simple<Window>(mut window: Window)
 where
     Window: FifoWindow<Value, Sum> + std::fmt::Debug,
@@ -68,6 +105,58 @@ where
     assert_eq!(window.query(), Value(5));
 }
 
+// Random rounds of pushes and pops of values made by `value`, checked
+// against a naive aggregate
+fn test_differential<V, BinOp, Window>(mut window: Window, value: impl Fn(i32) -> V)
+where
+    V: AbstractMonoid<BinOp> + Clone + PartialEq + std::fmt::Debug,
+    BinOp: Operator,
+    Window: FifoWindow<V, BinOp>,
+{
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut naive = VecDeque::new();
+    for _ in 0..1000 {
+        for _ in 0..rng.gen_range(0, 20) {
+            let v = value(rng.gen_range(-100, 100));
+            window.push(v.clone());
+            naive.push_back(v);
+            assert_eq!(
+                window.query(),
+                naive.iter().fold(V::identity(), |a, b| a.operate(b))
+            );
+            assert_eq!(window.len(), naive.len());
+        }
+        for _ in 0..rng.gen_range(0, 20) {
+            window.pop();
+            naive.pop_front();
+            assert_eq!(
+                window.query(),
+                naive.iter().fold(V::identity(), |a, b| a.operate(b))
+            );
+            assert_eq!(window.len(), naive.len());
+        }
+    }
+}
+
+// Pushes to a window which holds at most `capacity` values
+fn test_bounded<Window>(mut window: Window, capacity: usize)
+where
+    Window: FifoWindow<Value, Sum>,
+{
+    let mut naive = VecDeque::new();
+    for i in 0..100 {
+        window.push(Value(i));
+        naive.push_back(Value(i));
+        if naive.len() > capacity {
+            naive.pop_front();
+        }
+        assert_eq!(
+            window.query(),
+            naive.iter().fold(Value(0), |a, b| a.operate(b))
+        );
+    }
+}
+
 #[test]
 fn test_simple_rfs() {
     test_simple(RFS::<Value, Sum>::new());
@@ -83,12 +172,193 @@ fn test_simple_two_stacks() {
     test_simple(TwoStacks::<Value, Sum>::new());
 }
 
+#[test]
+fn test_simple_two_stacks_lite() {
+    test_simple(TwoStacksLite::<Value, Sum>::new());
+}
+
 #[test]
 fn test_simple_daba() {
     test_simple(DABA::<Value, Sum>::new());
 }
 
+#[test]
+fn test_simple_daba_deque() {
+    test_simple(DABA::<Value, Sum, Deque<_>>::new());
+}
+
+#[test]
+fn test_simple_daba_lite() {
+    test_simple(DABALite::<Value, Sum>::new());
+}
+
 #[test]
 fn test_simple_flat_fat() {
     test_simple(RA::<Value, Sum>::with_capacity(2));
 }
+
+#[test]
+fn test_simple_ioa() {
+    test_simple(IOA::<Value, Sum>::new());
+}
+
+#[test]
+fn test_differential_rfs() {
+    test_differential(RFS::<Value, Sum>::new(), Value);
+    test_differential(RFS::<Word, Concat>::new(), |v| Word(vec![v]));
+}
+
+#[test]
+fn test_differential_soe() {
+    test_differential(SOE::<Value, Sum>::new(), Value);
+}
+
+#[test]
+fn test_differential_two_stacks() {
+    test_differential(TwoStacks::<Value, Sum>::new(), Value);
+    test_differential(TwoStacks::<Word, Concat>::new(), |v| Word(vec![v]));
+}
+
+#[test]
+fn test_differential_two_stacks_lite() {
+    test_differential(TwoStacksLite::<Value, Sum>::new(), Value);
+    test_differential(TwoStacksLite::<Word, Concat>::new(), |v| Word(vec![v]));
+}
+
+#[test]
+fn test_differential_daba() {
+    test_differential(DABA::<Value, Sum>::new(), Value);
+    test_differential(DABA::<Word, Concat>::new(), |v| Word(vec![v]));
+}
+
+#[test]
+fn test_differential_ioa() {
+    test_differential(IOA::<Value, Sum>::new(), Value);
+    test_differential(IOA::<Word, Concat>::new(), |v| Word(vec![v]));
+}
+
+#[test]
+fn test_differential_daba_deque() {
+    test_differential(DABA::<Value, Sum, Deque<_>>::new(), Value);
+    test_differential(DABA::<Word, Concat, Deque<_>>::new(), |v| Word(vec![v]));
+}
+
+#[test]
+fn test_differential_daba_lite() {
+    test_differential(DABALite::<Value, Sum>::new(), Value);
+    test_differential(DABALite::<Word, Concat>::new(), |v| Word(vec![v]));
+}
+
+#[test]
+fn test_differential_flat_fat() {
+    test_differential(RA::<Value, Sum>::new(), Value);
+    test_differential(RA::<Word, Concat>::new(), |v| Word(vec![v]));
+}
+
+#[test]
+fn test_simple_with_capacity() {
+    test_simple(RFS::<Value, Sum>::with_capacity(2));
+    test_simple(SOE::<Value, Sum>::with_capacity(2));
+    test_simple(TwoStacks::<Value, Sum>::with_capacity(2));
+    test_simple(TwoStacksLite::<Value, Sum>::with_capacity(2));
+    test_simple(DABA::<Value, Sum>::with_capacity(2));
+    test_simple(DABA::<Value, Sum, Deque<_>>::with_capacity(2));
+    test_simple(DABALite::<Value, Sum>::with_capacity(2));
+    test_simple(IOA::<Value, Sum>::with_capacity(2));
+    test_simple(RA::<Value, Sum>::with_capacity(2));
+}
+
+#[test]
+fn test_simple_new_flat_fat() {
+    test_simple(RA::<Value, Sum>::new());
+}
+
+#[test]
+fn test_simple_array_two_stacks() {
+    test_simple(ArrayTwoStacks::<Value, Sum, [_; 4]>::new());
+}
+
+#[test]
+fn test_simple_array_daba() {
+    test_simple(ArrayDABA::<Value, Sum, [_; 4]>::new());
+}
+
+#[test]
+fn test_bounded_array_two_stacks() {
+    test_bounded(ArrayTwoStacks::<Value, Sum, [_; 8]>::new(), 8);
+    test_bounded(ArrayTwoStacks::<Value, Sum, [_; 8]>::with_capacity(5), 5);
+}
+
+#[test]
+fn test_bounded_array_daba() {
+    test_bounded(ArrayDABA::<Value, Sum, [_; 8]>::new(), 8);
+    test_bounded(ArrayDABA::<Value, Sum, [_; 8]>::with_capacity(5), 5);
+}
+
+#[test]
+fn test_try_push_array_two_stacks() {
+    let mut window = ArrayTwoStacks::<Value, Sum, [_; 2]>::new();
+    assert!(window.try_push(Value(1)).is_ok());
+    assert!(window.try_push(Value(2)).is_ok());
+    assert!(window.is_full());
+    assert_eq!(window.try_push(Value(3)).unwrap_err().element(), Value(3));
+    assert_eq!(window.query(), Value(3));
+}
+
+#[test]
+fn test_try_push_array_daba() {
+    let mut window = ArrayDABA::<Value, Sum, [_; 2]>::new();
+    assert!(window.try_push(Value(1)).is_ok());
+    assert!(window.try_push(Value(2)).is_ok());
+    assert!(window.is_full());
+    assert_eq!(window.try_push(Value(3)).unwrap_err().element(), Value(3));
+    assert_eq!(window.query(), Value(3));
+}
+
+thread_local! {
+    static DROPS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+// Sum which counts how many values are dropped
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Counted(i32);
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        DROPS.with(|drops| drops.set(drops.get() + 1));
+    }
+}
+
+impl Identity<Sum> for Counted {
+    fn identity() -> Counted {
+        Counted(0)
+    }
+}
+
+impl AbstractMagma<Sum> for Counted {
+    fn operate(&self, other: &Self) -> Self {
+        Counted(self.0 + other.0)
+    }
+}
+
+impl AbstractSemigroup<Sum> for Counted {}
+impl AbstractMonoid<Sum> for Counted {}
+
+// Every push and pop drops O(1) values, also when a rotation finishes
+#[test]
+fn test_incremental_drops_ioa() {
+    let drops = || DROPS.with(|drops| drops.get());
+    let mut window = IOA::<Counted, Sum>::new();
+    let mut max = 0;
+    for i in 0..10_000 {
+        let before = drops();
+        window.push(Counted(i));
+        max = max.max(drops() - before);
+        if i % 3 == 0 {
+            let before = drops();
+            window.pop();
+            max = max.max(drops() - before);
+        }
+    }
+    assert!(max <= 10, "{} values dropped by one operation", max);
+}
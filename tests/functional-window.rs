@@ -0,0 +1,189 @@
+use alga::general::AbstractMagma;
+use alga::general::AbstractMonoid;
+use alga::general::AbstractSemigroup;
+use alga::general::Identity;
+use alga::general::Operator;
+use swag::foa::*;
+use swag::*;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+use std::collections::VecDeque;
+use std::sync::mpsc;
+use std::thread;
+
+// Concatenation, which is not commutative
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Value(Vec<i32>);
+
+#[derive(Copy, Clone, Debug)]
+struct Concat;
+
+impl Operator for Concat {
+    fn operator_token() -> Concat {
+        Concat
+    }
+}
+
+impl Identity<Concat> for Value {
+    fn identity() -> Value {
+        Value(Vec::new())
+    }
+}
+
+impl AbstractMagma<Concat> for Value {
+    fn operate(&self, other: &Self) -> Self {
+        Value(self.0.iter().chain(other.0.iter()).cloned().collect())
+    }
+}
+
+impl AbstractSemigroup<Concat> for Value {}
+impl AbstractMonoid<Concat> for Value {}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct Count(u64);
+
+#[derive(Copy, Clone, Debug)]
+struct Sum;
+
+impl Operator for Sum {
+    fn operator_token() -> Sum {
+        Sum
+    }
+}
+
+impl Identity<Sum> for Count {
+    fn identity() -> Count {
+        Count(0)
+    }
+}
+
+impl AbstractMagma<Sum> for Count {
+    fn operate(&self, other: &Self) -> Self {
+        Count(self.0 + other.0)
+    }
+}
+
+impl AbstractSemigroup<Sum> for Count {}
+impl AbstractMonoid<Sum> for Count {}
+
+fn naive(values: &VecDeque<i32>) -> Value {
+    Value(values.iter().cloned().collect())
+}
+
+fn test_simple<Window>()
+where
+    Window: FunctionalWindow<Value, Concat>,
+{
+    let w0 = Window::new();
+    let w1 = w0.insert(Value(vec![1]));
+    let w2 = w1.insert(Value(vec![2]));
+    let w3 = w2.insert(Value(vec![3]));
+    let w4 = w3.evict();
+    assert_eq!(w0.query(), Value(vec![]));
+    assert_eq!(w1.query(), Value(vec![1]));
+    assert_eq!(w2.query(), Value(vec![1, 2]));
+    assert_eq!(w3.query(), Value(vec![1, 2, 3]));
+    assert_eq!(w4.query(), Value(vec![2, 3]));
+}
+
+// Random operations on random old versions, checked against a naive fold
+fn test_persistence<Window>()
+where
+    Window: FunctionalWindow<Value, Concat>,
+{
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut versions = vec![(Window::new(), VecDeque::new())];
+    for i in 0..5000 {
+        let (window, values) = if rng.gen_bool(0.9) {
+            versions.last().unwrap()
+        } else {
+            &versions[rng.gen_range(0, versions.len())]
+        };
+        let mut values = values.clone();
+        let window = if rng.gen_bool(0.55) {
+            values.push_back(i);
+            window.insert(Value(vec![i]))
+        } else {
+            values.pop_front();
+            window.evict()
+        };
+        versions.push((window, values));
+    }
+    for (window, values) in &versions {
+        assert_eq!(window.query(), naive(values));
+    }
+}
+
+#[test]
+fn test_simple_foa() {
+    test_simple::<FOA<Value, Concat>>();
+}
+
+#[test]
+fn test_persistence_foa() {
+    test_persistence::<FOA<Value, Concat>>();
+}
+
+// Neither rotating nor dropping a large window may overflow the stack
+#[test]
+fn test_large_foa() {
+    let mut window = FOA::<Count, Sum>::new();
+    for i in 0..1_000_000 {
+        window = window.insert(Count(i));
+    }
+    let full = window.clone();
+    for _ in 0..999_999 {
+        window = window.evict();
+    }
+    assert_eq!(window.query(), Count(999_999));
+    assert_eq!(full.query(), Count(499_999_500_000));
+    drop(full);
+}
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn test_send_sync_foa() {
+    assert_send_sync::<FOA<Value, Concat, ArcK>>();
+}
+
+// The writer publishes every version to readers, which query them while the
+// writer keeps inserting, and again after the writer has finished.
+#[test]
+fn test_threads_foa() {
+    let n = 10_000;
+    let size = 100;
+    let (senders, readers): (Vec<_>, Vec<_>) = (0..4)
+        .map(|_| {
+            let (tx, rx) = mpsc::channel::<(u64, FOA<Count, Sum, ArcK>)>();
+            let reader = thread::spawn(move || {
+                // Sum of the values in i-size+1..=i
+                let expected = |i: u64| Count((i.saturating_sub(size - 1)..=i).sum());
+                let versions = rx
+                    .iter()
+                    .inspect(|(i, window)| assert_eq!(window.query(), expected(*i)))
+                    .collect::<Vec<_>>();
+                for (i, window) in versions {
+                    assert_eq!(window.query(), expected(i));
+                }
+            });
+            (tx, reader)
+        })
+        .unzip();
+    let mut window = FOA::<Count, Sum, ArcK>::new();
+    for i in 0..n {
+        window = window.insert(Count(i));
+        if i >= size {
+            window = window.evict();
+        }
+        for tx in &senders {
+            tx.send((i, window.clone())).unwrap();
+        }
+    }
+    drop(senders);
+    for reader in readers {
+        reader.join().unwrap();
+    }
+}
@@ -0,0 +1,197 @@
+use alga::general::AbstractMagma;
+use alga::general::AbstractMonoid;
+use alga::general::AbstractSemigroup;
+use alga::general::Identity;
+use alga::general::Operator;
+use swag::daba::*;
+use swag::fiba::*;
+use swag::flat_fat::ra::*;
+use swag::keyed::ttl::*;
+use swag::keyed::*;
+use swag::time_fifo::*;
+use swag::two_stacks::*;
+use swag::*;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::time::Duration;
+
+// Concatenation, which is not commutative
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Value(Vec<i32>);
+
+#[derive(Copy, Clone, Debug)]
+struct Concat;
+
+impl Operator for Concat {
+    fn operator_token() -> Concat {
+        Concat
+    }
+}
+
+impl Identity<Concat> for Value {
+    fn identity() -> Value {
+        Value(Vec::new())
+    }
+}
+
+impl AbstractMagma<Concat> for Value {
+    fn operate(&self, other: &Self) -> Self {
+        Value(self.0.iter().chain(other.0.iter()).cloned().collect())
+    }
+}
+
+impl AbstractSemigroup<Concat> for Value {}
+impl AbstractMonoid<Concat> for Value {}
+
+type Time = u64;
+type Key = &'static str;
+
+fn test_simple<Window>()
+where
+    Window: TimeWindow<Time, Value, Concat>,
+{
+    let mut keyed = Keyed::<Key, Time, Value, Concat, Window>::new();
+    assert!(keyed.is_empty());
+    keyed.insert("a", 1, Value(vec![1])).unwrap();
+    keyed.insert("b", 2, Value(vec![2])).unwrap();
+    keyed.insert("a", 3, Value(vec![3])).unwrap();
+    keyed.insert("c", 5, Value(vec![5])).unwrap();
+    assert_eq!(keyed.len(), 3);
+    assert_eq!(keyed.query(&"a"), Some(Value(vec![1, 3])));
+    assert_eq!(keyed.query(&"d"), None);
+    keyed.evict_until(3);
+    // The window of b became empty
+    assert_eq!(keyed.len(), 2);
+    assert_eq!(keyed.query(&"a"), Some(Value(vec![3])));
+    assert_eq!(keyed.query(&"b"), None);
+    let mut results = keyed.iter().collect::<Vec<_>>();
+    results.sort_by_key(|(key, _)| **key);
+    assert_eq!(
+        results,
+        vec![(&"a", Value(vec![3])), (&"c", Value(vec![5]))]
+    );
+    assert_eq!(keyed.remove(&"c"), Some(Value(vec![5])));
+    assert_eq!(keyed.remove(&"c"), None);
+    keyed.evict_until(6);
+    assert!(keyed.is_empty());
+}
+
+// Inserts values in time order under random keys, evicts random time
+// intervals, and compares the aggregates of all keys with a naive window
+fn test_random<Window>()
+where
+    Window: TimeWindow<Time, Value, Concat>,
+{
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut keyed = Keyed::<u32, Time, Value, Concat, Window>::new();
+    let mut naive: BTreeMap<u32, Vec<(Time, i32)>> = BTreeMap::new();
+    let mut time = 0;
+    for i in 0..10_000 {
+        time += rng.gen_range(0, 3);
+        let key = rng.gen_range(0, 100);
+        keyed.insert(key, time, Value(vec![i])).unwrap();
+        naive.entry(key).or_default().push((time, i));
+        let watermark = time.saturating_sub(rng.gen_range(0, 200));
+        keyed.evict_until(watermark);
+        for values in naive.values_mut() {
+            values.retain(|(t, _)| *t >= watermark);
+        }
+        naive.retain(|_, values| !values.is_empty());
+        let results = keyed
+            .iter()
+            .map(|(key, agg)| (*key, agg))
+            .collect::<BTreeMap<_, _>>();
+        let expected = naive
+            .iter()
+            .map(|(key, values)| (*key, Value(values.iter().map(|(_, v)| *v).collect())))
+            .collect::<BTreeMap<_, _>>();
+        assert_eq!(results, expected);
+    }
+}
+
+#[test]
+fn test_simple_keyed() {
+    test_simple::<FIBA<Time, Value, Concat>>();
+    test_simple::<TimeFifo<Time, Value, Concat, TwoStacks<Value, Concat>>>();
+    test_simple::<TimeRA<Time, Value, Concat>>();
+}
+
+#[test]
+fn test_random_keyed() {
+    test_random::<FIBA<Time, Value, Concat>>();
+    test_random::<TimeFifo<Time, Value, Concat, TwoStacks<Value, Concat>>>();
+    test_random::<TimeRA<Time, Value, Concat>>();
+}
+
+fn test_expiry<Window>()
+where
+    Window: TimeWindow<Time, Value, Concat>,
+{
+    let clock = ManualClock::new();
+    let second = Duration::from_secs(1);
+    let mut keyed =
+        Expiring::<Key, Time, Value, Concat, Window, _>::new(10 * second, clock.clone());
+    let expired = Rc::new(RefCell::new(Vec::new()));
+    let callback = expired.clone();
+    keyed.on_expiry(move |key, agg| callback.borrow_mut().push((key, agg)));
+    keyed.insert("a", 1, Value(vec![1])).unwrap();
+    keyed.insert("b", 2, Value(vec![2])).unwrap();
+    clock.advance(5 * second);
+    keyed.insert("a", 3, Value(vec![3])).unwrap();
+    clock.advance(5 * second);
+    // b has been idle for exactly the time to live
+    assert_eq!(keyed.expire(), 0);
+    clock.advance(second);
+    assert_eq!(keyed.expire(), 1);
+    assert_eq!(*expired.borrow(), vec![("b", Value(vec![2]))]);
+    assert_eq!(keyed.query(&"b"), None);
+    assert_eq!(keyed.query(&"a"), Some(Value(vec![1, 3])));
+    // Inserts expire idle keys
+    clock.advance(5 * second);
+    keyed.insert("c", 4, Value(vec![4])).unwrap();
+    assert_eq!(
+        *expired.borrow(),
+        vec![("b", Value(vec![2])), ("a", Value(vec![1, 3]))]
+    );
+    assert_eq!(keyed.len(), 1);
+    // Keys whose windows became empty expire without an aggregate
+    keyed.evict_until(5);
+    assert!(keyed.is_empty());
+    clock.advance(11 * second);
+    assert_eq!(keyed.expire(), 0);
+    assert_eq!(expired.borrow().len(), 2);
+}
+
+#[test]
+fn test_expiry_keyed() {
+    test_expiry::<FIBA<Time, Value, Concat>>();
+    test_expiry::<TimeFifo<Time, Value, Concat, TwoStacks<Value, Concat>>>();
+    test_expiry::<TimeRA<Time, Value, Concat>>();
+    test_expiry::<TimeFifo<Time, Value, Concat, DABA<Value, Concat>>>();
+}
+
+// Values which the window of a key rejects do not refresh the key
+#[test]
+fn test_expiry_rejected() {
+    let clock = ManualClock::new();
+    let second = Duration::from_secs(1);
+    let mut keyed = Expiring::<
+        Key,
+        Time,
+        Value,
+        Concat,
+        TimeFifo<Time, Value, Concat, TwoStacks<Value, Concat>>,
+        _,
+    >::new(10 * second, clock.clone());
+    keyed.insert("a", 5, Value(vec![5])).unwrap();
+    clock.advance(5 * second);
+    assert_eq!(keyed.insert("a", 1, Value(vec![1])).unwrap_err().time, 1);
+    clock.advance(6 * second);
+    assert_eq!(keyed.expire(), 1);
+    assert!(keyed.is_empty());
+}
@@ -0,0 +1,368 @@
+use alga::general::AbstractGroup;
+use alga::general::AbstractLoop;
+use alga::general::AbstractMagma;
+use alga::general::AbstractMonoid;
+use alga::general::AbstractQuasigroup;
+use alga::general::AbstractSemigroup;
+use alga::general::Identity;
+use alga::general::Operator;
+use alga::general::TwoSidedInverse;
+use rand::Rng;
+use rand::SeedableRng;
+use std::ops::Range;
+use swag::flat_fat::ra_multi::*;
+use swag::slick_deque::*;
+use swag::slide_side::*;
+use swag::*;
+
+/// Concatenation of lists, which is non-commutative
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Value(Vec<i32>);
+
+#[derive(Copy, Clone, Debug)]
+struct Concat;
+
+impl Operator for Concat {
+    fn operator_token() -> Concat {
+        Concat
+    }
+}
+
+impl Identity<Concat> for Value {
+    fn identity() -> Value {
+        Value(Vec::new())
+    }
+}
+
+impl AbstractMagma<Concat> for Value {
+    fn operate(&self, other: &Self) -> Self {
+        Value(self.0.iter().chain(other.0.iter()).cloned().collect())
+    }
+}
+
+impl AbstractSemigroup<Concat> for Value {}
+impl AbstractMonoid<Concat> for Value {}
+
+/// Composition of permutations, which is a non-commutative group
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct Perm([usize; 4]);
+
+#[derive(Copy, Clone, Debug)]
+struct Compose;
+
+impl Operator for Compose {
+    fn operator_token() -> Compose {
+        Compose
+    }
+}
+
+impl Identity<Compose> for Perm {
+    fn identity() -> Perm {
+        Perm([0, 1, 2, 3])
+    }
+}
+
+impl AbstractMagma<Compose> for Perm {
+    fn operate(&self, other: &Self) -> Self {
+        let mut perm = [0; 4];
+        for (i, p) in perm.iter_mut().enumerate() {
+            *p = other.0[self.0[i]];
+        }
+        Perm(perm)
+    }
+}
+
+impl TwoSidedInverse<Compose> for Perm {
+    fn two_sided_inverse(&self) -> Perm {
+        let mut perm = [0; 4];
+        for (i, &p) in self.0.iter().enumerate() {
+            perm[p] = i;
+        }
+        Perm(perm)
+    }
+}
+
+impl AbstractSemigroup<Compose> for Perm {}
+impl AbstractMonoid<Compose> for Perm {}
+impl AbstractQuasigroup<Compose> for Perm {}
+impl AbstractLoop<Compose> for Perm {}
+impl AbstractGroup<Compose> for Perm {}
+
+/// Maximum, which is a selection operator
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct Max(i32);
+
+#[derive(Copy, Clone, Debug)]
+struct MaxOp;
+
+impl Operator for MaxOp {
+    fn operator_token() -> MaxOp {
+        MaxOp
+    }
+}
+
+impl Identity<MaxOp> for Max {
+    fn identity() -> Max {
+        Max(i32::MIN)
+    }
+}
+
+impl AbstractMagma<MaxOp> for Max {
+    fn operate(&self, other: &Self) -> Self {
+        Max(self.0.max(other.0))
+    }
+}
+
+impl AbstractSemigroup<MaxOp> for Max {}
+impl AbstractMonoid<MaxOp> for Max {}
+
+/// Aggregates the items which are `start` up to `end` insertions old
+fn naive<V, BinOp>(history: &[V], query: &Range<Count>) -> V
+where
+    V: AbstractMonoid<BinOp>,
+    BinOp: Operator,
+{
+    let len = history.len();
+    let from = len.saturating_sub(query.end as usize);
+    let to = len.saturating_sub(query.start as usize);
+    history[from..to.max(from)]
+        .iter()
+        .fold(V::identity(), |acc, v| acc.operate(v))
+}
+
+fn random_range(rng: &mut impl Rng) -> Range<Count> {
+    let start = rng.gen_range(0, 20);
+    start..start + rng.gen_range(0, 40)
+}
+
+fn random_size(rng: &mut impl Rng) -> Range<Count> {
+    0..rng.gen_range(0, 40)
+}
+
+fn random_max(rng: &mut impl Rng) -> Max {
+    Max(rng.gen_range(0, 100))
+}
+
+fn random_perm(rng: &mut impl Rng) -> Perm {
+    let mut perm = Perm::identity().0;
+    perm.swap(rng.gen_range(0, 4), rng.gen_range(0, 4));
+    Perm(perm)
+}
+
+fn test_simple<Window>()
+where
+    Window: MultiWindow<Perm, Compose>,
+{
+    let perms = [Perm([1, 0, 2, 3]), Perm([0, 2, 1, 3]), Perm([0, 1, 3, 2])];
+    let mut window = Window::new(&[0..2, 0..3, 1..3]);
+    for i in 0..5 {
+        window.insert(perms[i % 3]);
+    }
+    // Items 3 and 4 are perms[0] and perms[1], item 2 is perms[2]
+    assert_eq!(window.query(0), perms[0].operate(&perms[1]));
+    assert_eq!(
+        window.query(1),
+        perms[2].operate(&perms[0]).operate(&perms[1])
+    );
+    assert_eq!(window.query(2), perms[2].operate(&perms[0]));
+}
+
+fn test_dynamic<Window>()
+where
+    Window: MultiWindow<Perm, Compose>,
+{
+    let perm = Perm([1, 2, 3, 0]);
+    let mut window = Window::new(&[0..1, 0..2]);
+    for _ in 0..10 {
+        window.insert(perm);
+    }
+    window.remove_query(0);
+    assert_eq!(
+        window.results().collect::<Vec<_>>(),
+        vec![(1, &perm.operate(&perm))]
+    );
+    // Only the two most recent items were kept by the window
+    let id = window.add_query(0..4);
+    assert_eq!(id, 0);
+    assert_eq!(window.query(id), perm.operate(&perm));
+    window.insert(perm);
+    assert_eq!(window.query(id), perm.operate(&perm).operate(&perm));
+    window.insert(perm);
+    assert_eq!(window.query(id), Perm::identity());
+    assert_eq!(window.add_query(1..3), 2);
+    assert_eq!(window.results().count(), 3);
+}
+
+/// Compares a window against a naive fold while randomly inserting items and
+/// adding and removing queries
+fn test_differential<Window, Value, BinOp>(
+    random_range: impl Fn(&mut rand::rngs::StdRng) -> Range<Count>,
+    random_value: impl Fn(&mut rand::rngs::StdRng) -> Value,
+) where
+    Window: MultiWindow<Value, BinOp>,
+    Value: AbstractMonoid<BinOp> + Copy + std::fmt::Debug,
+    BinOp: Operator,
+{
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+    for _ in 0..50 {
+        let mut queries = (0..rng.gen_range(1, 5))
+            .map(|_| Some(random_range(&mut rng)))
+            .collect::<Vec<Option<Range<Count>>>>();
+        let ranges = queries.iter().flatten().cloned().collect::<Vec<_>>();
+        let mut window = Window::new(&ranges);
+        let mut history = Vec::new();
+        for _ in 0..rng.gen_range(0, 200) {
+            match rng.gen_range(0, 20) {
+                0 => {
+                    // Queries which grow the window are covered by test_dynamic
+                    let size = ranges.iter().map(|q| q.end).max().unwrap_or(0);
+                    let range = random_range(&mut rng);
+                    let range = range.start..range.end.min(size).max(range.start);
+                    let id = window.add_query(range.clone());
+                    if id == queries.len() {
+                        queries.push(None);
+                    }
+                    assert!(queries[id].is_none());
+                    queries[id] = Some(range);
+                }
+                1 => {
+                    let id = rng.gen_range(0, queries.len());
+                    if queries[id].is_some() {
+                        window.remove_query(id);
+                        queries[id] = None;
+                    }
+                }
+                _ => {
+                    let value = random_value(&mut rng);
+                    window.insert(value);
+                    history.push(value);
+                }
+            }
+            let results = window
+                .results()
+                .map(|(id, agg)| (id, *agg))
+                .collect::<Vec<_>>();
+            let expected = queries
+                .iter()
+                .enumerate()
+                .filter_map(|(id, query)| query.as_ref().map(|q| (id, naive(&history, q))))
+                .collect::<Vec<_>>();
+            assert_eq!(results, expected);
+            for (id, agg) in expected {
+                assert_eq!(window.query(id), agg);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_multi_ra_concat() {
+    let mut window = MultiRA::<Value, Concat>::new(&[0..2, 0..3, 1..3]);
+    for i in 0..5 {
+        window.insert(Value(vec![i]));
+    }
+    assert_eq!(window.query(0), Value(vec![3, 4]));
+    assert_eq!(window.query(1), Value(vec![2, 3, 4]));
+    assert_eq!(window.query(2), Value(vec![2, 3]));
+}
+
+#[test]
+fn test_simple_multi_ra() {
+    test_simple::<MultiRA<Perm, Compose>>();
+}
+
+#[test]
+fn test_simple_slide_side() {
+    test_simple::<SlideSide<Perm, Compose>>();
+}
+
+#[test]
+fn test_dynamic_multi_ra() {
+    test_dynamic::<MultiRA<Perm, Compose>>();
+}
+
+#[test]
+fn test_dynamic_slide_side() {
+    test_dynamic::<SlideSide<Perm, Compose>>();
+}
+
+#[test]
+fn test_simple_slick_deque_inv() {
+    test_simple::<SlickDequeInv<Perm, Compose>>();
+}
+
+#[test]
+fn test_dynamic_slick_deque_inv() {
+    test_dynamic::<SlickDequeInv<Perm, Compose>>();
+}
+
+#[test]
+fn test_simple_slick_deque_non_inv() {
+    let mut window = SlickDequeNonInv::<Max, MaxOp>::new(&[0..1, 0..3, 0..0]);
+    assert_eq!(window.query(1), Max::identity());
+    for &v in [5, 1, 3, 2, 4, 0].iter() {
+        window.insert(Max(v));
+    }
+    assert_eq!(window.query(0), Max(0));
+    assert_eq!(window.query(1), Max(4));
+    assert_eq!(window.query(2), Max::identity());
+}
+
+#[test]
+fn test_dynamic_slick_deque_non_inv() {
+    let mut window = SlickDequeNonInv::<Max, MaxOp>::new(&[0..1, 0..2]);
+    for &v in [5, 1, 3, 2].iter() {
+        window.insert(Max(v));
+    }
+    window.remove_query(0);
+    assert_eq!(window.results().collect::<Vec<_>>(), vec![(1, &Max(3))]);
+    // Only the two most recent items were kept by the window
+    let id = window.add_query(0..4);
+    assert_eq!(id, 0);
+    assert_eq!(window.query(id), Max(3));
+    window.insert(Max(1));
+    assert_eq!(window.query(id), Max(3));
+    assert_eq!(window.query(1), Max(2));
+}
+
+// Queries which skip the most recent items are not affected by the items which
+// they skip
+#[test]
+fn test_slick_deque_non_inv_offset() {
+    let mut window = SlickDequeNonInv::<Max, MaxOp>::new(&[0..2, 1..2, 2..4]);
+    for &v in [1, 3, 2, 9].iter() {
+        window.insert(Max(v));
+    }
+    assert_eq!(window.query(0), Max(9));
+    assert_eq!(window.query(1), Max(2));
+    assert_eq!(window.query(2), Max(3));
+    // The new lane starts from the items which are kept by the window
+    let id = window.add_query(1..4);
+    assert_eq!(window.query(id), Max(3));
+    window.insert(Max(0));
+    assert_eq!(window.query(1), Max(9));
+    assert_eq!(window.query(2), Max(3));
+    assert_eq!(window.query(id), Max(9));
+}
+
+#[test]
+fn test_differential_multi_ra() {
+    test_differential::<MultiRA<_, _>, _, Compose>(random_range, random_perm);
+    test_differential::<MultiRA<_, _>, _, MaxOp>(random_size, random_max);
+}
+
+#[test]
+fn test_differential_slide_side() {
+    test_differential::<SlideSide<_, _>, _, Compose>(random_range, random_perm);
+}
+
+#[test]
+fn test_differential_slick_deque_inv() {
+    test_differential::<SlickDequeInv<_, _>, _, Compose>(random_range, random_perm);
+}
+
+#[test]
+fn test_differential_slick_deque_non_inv() {
+    test_differential::<SlickDequeNonInv<_, _>, _, MaxOp>(random_size, random_max);
+    test_differential::<SlickDequeNonInv<_, _>, _, MaxOp>(random_range, random_max);
+}
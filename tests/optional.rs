@@ -0,0 +1,182 @@
+use alga::general::AbstractMagma;
+use alga::general::AbstractSemigroup;
+use alga::general::Operator;
+use swag::daba::*;
+use swag::fiba::*;
+use swag::flat_fat::fat::*;
+use swag::flat_fat::flat_fat::*;
+use swag::flat_fat::ra_fifo::*;
+use swag::ops::Optional;
+use swag::rfs::*;
+use swag::two_stacks::*;
+use swag::*;
+
+/// A value without an identity element under any of the operators below
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+struct Word(String);
+
+#[derive(Copy, Clone, Debug)]
+struct First;
+
+#[derive(Copy, Clone, Debug)]
+struct Last;
+
+#[derive(Copy, Clone, Debug)]
+struct Max;
+
+impl Operator for First {
+    fn operator_token() -> First {
+        First
+    }
+}
+
+impl Operator for Last {
+    fn operator_token() -> Last {
+        Last
+    }
+}
+
+impl Operator for Max {
+    fn operator_token() -> Max {
+        Max
+    }
+}
+
+impl AbstractMagma<First> for Word {
+    fn operate(&self, _: &Self) -> Self {
+        self.clone()
+    }
+}
+
+impl AbstractMagma<Last> for Word {
+    fn operate(&self, other: &Self) -> Self {
+        other.clone()
+    }
+}
+
+impl AbstractMagma<Max> for Word {
+    fn operate(&self, other: &Self) -> Self {
+        std::cmp::max(self, other).clone()
+    }
+}
+
+impl AbstractSemigroup<First> for Word {}
+impl AbstractSemigroup<Last> for Word {}
+impl AbstractSemigroup<Max> for Word {}
+
+fn word<BinOp>(s: &str) -> Optional<Word, BinOp> {
+    Optional::new(Word(s.to_string()))
+}
+
+fn test_first<Window>(mut window: Window)
+where
+    Window: FifoWindow<Optional<Word, First>, First>,
+{
+    assert_eq!(window.query(), Optional::none());
+    window.push(word("a"));
+    assert_eq!(window.query(), word("a"));
+
+    window.push(word("b"));
+    assert_eq!(window.query(), word("a"));
+
+    window.push(word("c"));
+    assert_eq!(window.query(), word("a"));
+
+    window.pop();
+    assert_eq!(window.query(), word("b"));
+}
+
+fn test_last<Window>(mut window: Window)
+where
+    Window: FifoWindow<Optional<Word, Last>, Last>,
+{
+    assert_eq!(window.query(), Optional::none());
+    window.push(word("c"));
+    assert_eq!(window.query(), word("c"));
+
+    window.push(word("a"));
+    assert_eq!(window.query(), word("a"));
+
+    window.push(word("b"));
+    assert_eq!(window.query(), word("b"));
+
+    window.pop();
+    assert_eq!(window.query(), word("b"));
+}
+
+#[test]
+fn test_optional_rfs() {
+    test_first(RFS::new());
+    test_last(RFS::new());
+}
+
+#[test]
+fn test_optional_two_stacks() {
+    test_first(TwoStacks::new());
+    test_last(TwoStacks::new());
+}
+
+#[test]
+fn test_optional_daba() {
+    test_first(DABA::<_, _>::new());
+    test_last(DABA::<_, _>::new());
+}
+
+#[test]
+fn test_optional_flat_fat() {
+    test_first(RA::with_capacity(2));
+    test_last(RA::with_capacity(2));
+}
+
+#[test]
+fn test_optional_fiba() {
+    let mut tree: FIBA<i32, Optional<Word, First>, First> = FIBA::new();
+    assert_eq!(tree.query(), Optional::none());
+    tree.insert(2, word("b"));
+    tree.insert(3, word("c"));
+    tree.insert(1, word("a"));
+    assert_eq!(tree.query(), word("a"));
+    assert_eq!(tree.range_query(2..3), word("b"));
+    tree.evict(1);
+    assert_eq!(tree.query(), word("b"));
+}
+
+#[test]
+fn test_optional_max() {
+    let mut window: TwoStacks<Optional<Word, Max>, Max> = TwoStacks::new();
+    window.push(word("b"));
+    window.push(word("c"));
+    window.push(word("a"));
+    assert_eq!(window.query(), word("c"));
+    window.pop();
+    window.pop();
+    assert_eq!(window.query(), word("a"));
+}
+
+#[test]
+fn test_optional_prefix_suffix() {
+    let words = ["a", "b", "c", "d", "e"]
+        .iter()
+        .map(|s| word(s))
+        .collect::<Vec<Optional<Word, Last>>>();
+    let mut fat = FlatFAT::new(&words);
+    assert_eq!(fat.prefix(2), word("c"));
+    assert_eq!(fat.suffix(2), word("e"));
+    fat.update(&[(3, Optional::none()), (4, Optional::none())]);
+    assert_eq!(fat.aggregate(), word("c"));
+    assert_eq!(fat.suffix(2), word("c"));
+}
+
+#[test]
+fn test_optional_conversions() {
+    let some: Optional<Word, First> = Word("a".to_string()).into();
+    assert!(some.is_some());
+    assert_eq!(some.get(), Some(&Word("a".to_string())));
+    assert_eq!(Option::from(some.clone()), Some(Word("a".to_string())));
+    assert_eq!(some.into_inner(), Some(Word("a".to_string())));
+
+    let none: Optional<Word, First> = None.into();
+    assert!(none.is_none());
+    assert_eq!(none, Optional::default());
+    assert_eq!(Option::<Word>::from(none), None);
+}
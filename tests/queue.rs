@@ -0,0 +1,130 @@
+use swag::daba::array_queue::ArrayQueue;
+use swag::daba::chunked_array_queue::ChunkedArrayQueue;
+use swag::daba::queue::Deque;
+use swag::daba::queue::Queue;
+
+use std::rc::Rc;
+
+fn test_push_pop<Q: Queue<usize>>() {
+    let mut queue = Q::with_capacity(2000);
+    assert!(queue.is_empty());
+    assert_eq!(queue.pop_front(), None);
+    for round in 0..10 {
+        for i in 0..1000 {
+            queue.push_back(round * 1000 + i);
+        }
+        for i in 0..900 {
+            assert_eq!(queue.pop_front(), Some(round * 900 + i));
+        }
+        assert_eq!(queue.len(), (round + 1) * 100);
+    }
+}
+
+fn test_pointers<Q: Queue<usize>>() {
+    let mut queue = Q::with_capacity(200);
+    let end = queue.end();
+    for i in 0..200 {
+        queue.push_back(i);
+    }
+    // The end pointer refers to the first pushed value
+    assert_eq!(end, queue.begin());
+    let mut p = queue.begin();
+    unsafe {
+        for i in 0..200 {
+            assert_eq!(*queue.read(p), i);
+            *queue.write(p) += 1000;
+            p = queue.next(p);
+        }
+    }
+    assert_eq!(p, queue.end());
+    assert_eq!(queue.front(), Some(&1000));
+    assert_eq!(queue.back(), Some(&1199));
+    // Pointers stay valid when values before them are popped
+    let mid = unsafe { queue.prev(queue.prev(p)) };
+    for i in 0..198 {
+        assert_eq!(queue.pop_front(), Some(1000 + i));
+    }
+    assert_eq!(mid, queue.begin());
+    unsafe {
+        assert_eq!(*queue.read(mid), 1198);
+        assert_eq!(*queue.read(queue.next(mid)), 1199);
+    }
+}
+
+#[test]
+fn test_push_pop_chunked_array_queue() {
+    test_push_pop::<ChunkedArrayQueue<usize>>();
+}
+
+#[test]
+fn test_push_pop_deque() {
+    test_push_pop::<Deque<usize>>();
+}
+
+#[test]
+fn test_pointers_chunked_array_queue() {
+    test_pointers::<ChunkedArrayQueue<usize>>();
+}
+
+#[test]
+fn test_pointers_deque() {
+    test_pointers::<Deque<usize>>();
+}
+
+#[test]
+fn test_push_pop_array_queue() {
+    test_push_pop::<ArrayQueue<[usize; 2048]>>();
+}
+
+#[test]
+fn test_pointers_array_queue() {
+    test_pointers::<ArrayQueue<[usize; 256]>>();
+}
+
+#[test]
+fn test_full_array_queue() {
+    let mut queue = ArrayQueue::<[usize; 4]>::with_capacity(3);
+    for i in 0..3 {
+        queue.push_back(i);
+    }
+    assert!(queue.is_full());
+    assert_eq!(queue.pop_front(), Some(0));
+    assert!(!queue.is_full());
+}
+
+#[test]
+fn test_drop_chunked_array_queue() {
+    let value = Rc::new(());
+    let mut queue = ChunkedArrayQueue::new();
+    for _ in 0..1000 {
+        queue.push_back(value.clone());
+    }
+    for _ in 0..500 {
+        queue.pop_front();
+    }
+    assert_eq!(Rc::strong_count(&value), 501);
+    drop(queue);
+    assert_eq!(Rc::strong_count(&value), 1);
+}
+
+#[test]
+fn test_drop_array_queue() {
+    // Popped values are moved out, so values need not be Clone
+    struct Token(Rc<()>);
+    let value = Rc::new(());
+    let mut queue = ArrayQueue::<[Token; 64]>::with_capacity(40);
+    for _ in 0..100 {
+        if queue.is_full() {
+            drop(queue.pop_front());
+        }
+        queue.push_back(Token(value.clone()));
+    }
+    assert_eq!(Rc::strong_count(&value), 41);
+    let token = queue.pop_front().unwrap();
+    assert!(Rc::ptr_eq(&token.0, &value));
+    assert_eq!(Rc::strong_count(&value), 41);
+    drop(token);
+    assert_eq!(Rc::strong_count(&value), 40);
+    drop(queue);
+    assert_eq!(Rc::strong_count(&value), 1);
+}
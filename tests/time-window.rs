@@ -0,0 +1,256 @@
+use alga::general::AbstractMagma;
+use alga::general::AbstractMonoid;
+use alga::general::AbstractSemigroup;
+use alga::general::Identity;
+use alga::general::Operator;
+use swag::bounded_disorder::*;
+use swag::daba::*;
+use swag::fiba::*;
+use swag::flat_fat::ra::*;
+use swag::ioa::*;
+use swag::time_fifo::*;
+use swag::two_stacks::*;
+use swag::*;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+use std::collections::VecDeque;
+
+// Concatenation, which is not commutative
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Value(Vec<i32>);
+
+#[derive(Copy, Clone, Debug)]
+struct Concat;
+
+impl Operator for Concat {
+    fn operator_token() -> Concat {
+        Concat
+    }
+}
+
+impl Identity<Concat> for Value {
+    fn identity() -> Value {
+        Value(Vec::new())
+    }
+}
+
+impl AbstractMagma<Concat> for Value {
+    fn operate(&self, other: &Self) -> Self {
+        Value(self.0.iter().chain(other.0.iter()).cloned().collect())
+    }
+}
+
+impl AbstractSemigroup<Concat> for Value {}
+impl AbstractMonoid<Concat> for Value {}
+
+type Time = u64;
+
+fn naive(values: &VecDeque<(Time, i32)>) -> Value {
+    Value(values.iter().map(|(_, v)| *v).collect())
+}
+
+fn test_simple<Window>()
+where
+    Window: TimeWindow<Time, Value, Concat>,
+{
+    let mut window = Window::new();
+    assert_eq!(window.query(), Value(vec![]));
+    assert_eq!(window.oldest(), None);
+    window.insert(1, Value(vec![1])).unwrap();
+    window.insert(3, Value(vec![3])).unwrap();
+    window.insert(3, Value(vec![4])).unwrap();
+    window.insert(5, Value(vec![5])).unwrap();
+    assert_eq!(window.query(), Value(vec![1, 3, 4, 5]));
+    assert_eq!(window.oldest(), Some(1));
+    window.evict(3);
+    assert_eq!(window.query(), Value(vec![3, 4, 5]));
+    assert_eq!(window.oldest(), Some(3));
+    window.evict(4);
+    assert_eq!(window.query(), Value(vec![5]));
+    assert_eq!(window.oldest(), Some(5));
+    window.evict(6);
+    assert_eq!(window.oldest(), None);
+}
+
+// In-order inserts and evictions of random time intervals
+fn test_in_order<Window>()
+where
+    Window: TimeWindow<Time, Value, Concat>,
+{
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut window = Window::new();
+    let mut naive_window = VecDeque::new();
+    let mut time = 0;
+    for i in 0..10_000 {
+        time += rng.gen_range(0, 3);
+        window.insert(time, Value(vec![i])).unwrap();
+        naive_window.push_back((time, i));
+        let watermark = time.saturating_sub(rng.gen_range(0, 50));
+        window.evict(watermark);
+        while matches!(naive_window.front(), Some((t, _)) if *t < watermark) {
+            naive_window.pop_front();
+        }
+        assert_eq!(window.query(), naive(&naive_window));
+    }
+}
+
+fn test_out_of_order<Window>()
+where
+    Window: TimeWindow<Time, Value, Concat>,
+{
+    let mut window = Window::new();
+    window.insert(5, Value(vec![5])).unwrap();
+    let error = window.insert(4, Value(vec![4])).unwrap_err();
+    assert_eq!(error.time, 4);
+    assert_eq!(error.value, Value(vec![4]));
+    assert_eq!(window.query(), Value(vec![5]));
+    // Times before evicted values are out of order as well
+    window.evict(10);
+    assert!(window.insert(4, Value(vec![4])).is_err());
+    assert_eq!(window.query(), Value(vec![]));
+}
+
+#[test]
+fn test_simple_time_fifo() {
+    test_simple::<TimeFifo<Time, Value, Concat, TwoStacks<Value, Concat>>>();
+    test_simple::<TimeFifo<Time, Value, Concat, DABA<Value, Concat>>>();
+    test_simple::<TimeFifo<Time, Value, Concat, IOA<Value, Concat>>>();
+}
+
+#[test]
+fn test_simple_time_ra() {
+    test_simple::<TimeRA<Time, Value, Concat>>();
+}
+
+#[test]
+fn test_in_order_time_fifo() {
+    test_in_order::<TimeFifo<Time, Value, Concat, TwoStacks<Value, Concat>>>();
+    test_in_order::<TimeFifo<Time, Value, Concat, DABA<Value, Concat>>>();
+    test_in_order::<TimeFifo<Time, Value, Concat, IOA<Value, Concat>>>();
+}
+
+#[test]
+fn test_in_order_time_ra() {
+    test_in_order::<TimeRA<Time, Value, Concat>>();
+}
+
+#[test]
+fn test_out_of_order_time_fifo() {
+    test_out_of_order::<TimeFifo<Time, Value, Concat, TwoStacks<Value, Concat>>>();
+    test_out_of_order::<TimeFifo<Time, Value, Concat, DABA<Value, Concat>>>();
+    test_out_of_order::<TimeFifo<Time, Value, Concat, IOA<Value, Concat>>>();
+}
+
+#[test]
+fn test_out_of_order_time_ra() {
+    test_out_of_order::<TimeRA<Time, Value, Concat>>();
+}
+
+// A full FIFO window evicts its oldest value on push, and the time of the
+// value must be evicted with it
+fn test_full<Window>()
+where
+    Window: TimeWindow<Time, Value, Concat>,
+{
+    let mut window = Window::new();
+    window.insert(1, Value(vec![1])).unwrap();
+    window.insert(2, Value(vec![2])).unwrap();
+    window.insert(3, Value(vec![3])).unwrap();
+    assert_eq!(window.query(), Value(vec![2, 3]));
+    assert_eq!(window.oldest(), Some(2));
+    window.evict(3);
+    assert_eq!(window.query(), Value(vec![3]));
+    window.insert(4, Value(vec![4])).unwrap();
+    window.insert(5, Value(vec![5])).unwrap();
+    window.evict(5);
+    assert_eq!(window.query(), Value(vec![5]));
+    assert_eq!(window.oldest(), Some(5));
+}
+
+#[test]
+fn test_full_time_fifo() {
+    test_full::<TimeFifo<Time, Value, Concat, ArrayTwoStacks<Value, Concat, [_; 2]>>>();
+    test_full::<TimeFifo<Time, Value, Concat, ArrayDABA<Value, Concat, [_; 2]>>>();
+}
+
+// Inserts of random times at most `lateness` before the newest time, and
+// evictions of random time intervals
+fn test_bounded_disorder<Window>(mut window: Window, lateness: Time)
+where
+    Window: TimeWindow<Time, Value, Concat>,
+{
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut naive_window: Vec<(Time, i32)> = Vec::new();
+    let mut newest: Time = 0;
+    for i in 0..10_000 {
+        newest += rng.gen_range(0, 3);
+        let time = newest.saturating_sub(rng.gen_range(0, lateness));
+        window.insert(time, Value(vec![i])).unwrap();
+        // Values at the same time are aggregated in insertion order
+        let index = naive_window.iter().rposition(|(t, _)| *t <= time);
+        naive_window.insert(index.map_or(0, |index| index + 1), (time, i));
+        let watermark = newest.saturating_sub(lateness + rng.gen_range(0, 50));
+        window.evict(watermark);
+        naive_window.retain(|(t, _)| *t >= watermark);
+        let expected = Value(naive_window.iter().map(|(_, v)| *v).collect());
+        assert_eq!(window.query(), expected);
+    }
+}
+
+#[test]
+fn test_simple_fiba() {
+    test_simple::<FIBA<Time, Value, Concat>>();
+}
+
+#[test]
+fn test_in_order_fiba() {
+    test_in_order::<FIBA<Time, Value, Concat>>();
+}
+
+#[test]
+fn test_bounded_disorder_fiba() {
+    test_bounded_disorder(FIBA::<Time, Value, Concat>::new(), 100);
+}
+
+#[test]
+fn test_simple_bounded_disorder() {
+    test_simple::<BoundedDisorder<Time, Value, Concat, TwoStacks<Value, Concat>>>();
+    test_simple::<BoundedDisorder<Time, Value, Concat, DABA<Value, Concat>>>();
+}
+
+#[test]
+fn test_in_order_bounded_disorder() {
+    test_in_order::<BoundedDisorder<Time, Value, Concat, TwoStacks<Value, Concat>>>();
+    test_in_order::<BoundedDisorder<Time, Value, Concat, DABA<Value, Concat>>>();
+}
+
+#[test]
+fn test_out_of_order_bounded_disorder() {
+    test_out_of_order::<BoundedDisorder<Time, Value, Concat, TwoStacks<Value, Concat>>>();
+    let mut window = BoundedDisorder::<Time, Value, Concat, DABA<Value, Concat>>::with_lateness(3);
+    window.insert(5, Value(vec![5])).unwrap();
+    window.insert(3, Value(vec![3])).unwrap();
+    window.insert(9, Value(vec![9])).unwrap();
+    // The watermark has passed 5, but not 7
+    window.insert(7, Value(vec![7])).unwrap();
+    assert!(window.insert(4, Value(vec![4])).is_err());
+    assert_eq!(window.query(), Value(vec![3, 5, 7, 9]));
+}
+
+#[test]
+fn test_bounded_disorder_lateness() {
+    for &lateness in [1, 10, 100].iter() {
+        test_bounded_disorder(
+            BoundedDisorder::<Time, Value, Concat, TwoStacks<Value, Concat>>::with_lateness(
+                lateness,
+            ),
+            lateness,
+        );
+        test_bounded_disorder(
+            BoundedDisorder::<Time, Value, Concat, DABA<Value, Concat>>::with_lateness(lateness),
+            lateness,
+        );
+    }
+}
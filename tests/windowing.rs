@@ -0,0 +1,882 @@
+use alga::general::AbstractMagma;
+use alga::general::AbstractMonoid;
+use alga::general::AbstractSemigroup;
+use alga::general::Identity;
+use alga::general::Operator;
+use swag::fiba::*;
+use swag::time_fifo::*;
+use swag::two_stacks::*;
+use swag::windowing::data_driven::*;
+use swag::windowing::session::*;
+use swag::windowing::slicing::*;
+use swag::windowing::*;
+use swag::*;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+use std::collections::BTreeMap;
+
+// Concatenation, which is not commutative
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Value(Vec<i32>);
+
+#[derive(Copy, Clone, Debug)]
+struct Concat;
+
+impl Operator for Concat {
+    fn operator_token() -> Concat {
+        Concat
+    }
+}
+
+impl Identity<Concat> for Value {
+    fn identity() -> Value {
+        Value(Vec::new())
+    }
+}
+
+impl AbstractMagma<Concat> for Value {
+    fn operate(&self, other: &Self) -> Self {
+        Value(self.0.iter().chain(other.0.iter()).cloned().collect())
+    }
+}
+
+impl AbstractSemigroup<Concat> for Value {}
+impl AbstractMonoid<Concat> for Value {}
+
+type Time = u64;
+
+type Window = FIBA<Time, Value, Concat>;
+
+// Aggregates the values of a window, in time order and then insertion order
+fn naive(values: &[(Time, i32)], start: Time, end: Time) -> Value {
+    let mut window: Vec<(Time, i32)> = values
+        .iter()
+        .filter(|(t, _)| start <= *t && *t < end)
+        .cloned()
+        .collect();
+    window.sort_by_key(|(t, _)| *t);
+    Value(window.into_iter().map(|(_, v)| v).collect())
+}
+
+// Inserts values up to `disorder` time units before the newest time, and
+// advances the watermark to `disorder` time units before the newest time.
+// Windows without values are not emitted.
+fn test_random<Assigner>(assigner: Assigner, windows: impl Iterator<Item = (Time, Time)>)
+where
+    Assigner: WindowAssigner<Time>,
+{
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut driver = Driver::<Time, Value, Concat, Window, Assigner>::new(assigner);
+    let disorder = 20;
+    let mut values = Vec::new();
+    let mut results = Vec::new();
+    // The first value is not the oldest
+    let mut newest: Time = 1000;
+    for i in 0..1000 {
+        newest += rng.gen_range(0, 3);
+        let time = newest.saturating_sub(rng.gen_range(0, disorder));
+        driver.insert(time, Value(vec![i])).unwrap();
+        values.push((time, i));
+        results.extend(driver.advance(newest.saturating_sub(disorder)));
+    }
+    results.extend(driver.advance(newest + 1));
+    let expected = windows
+        .take_while(|(_, end)| *end <= newest + 1)
+        .map(|(start, end)| (start, end, naive(&values, start, end)))
+        .filter(|(_, _, agg)| !agg.0.is_empty())
+        .collect::<Vec<_>>();
+    assert!(!expected.is_empty());
+    assert_eq!(results, expected);
+}
+
+#[test]
+fn test_tumbling() {
+    let mut driver = Driver::<Time, Value, Concat, Window, _>::new(Tumbling { size: 10 });
+    driver.insert(3, Value(vec![3])).unwrap();
+    driver.insert(12, Value(vec![12])).unwrap();
+    driver.insert(1, Value(vec![1])).unwrap();
+    driver.insert(10, Value(vec![10])).unwrap();
+    assert_eq!(driver.advance(9), vec![]);
+    assert_eq!(driver.advance(10), vec![(0, 10, Value(vec![1, 3]))]);
+    // The first window has been emitted and its values evicted
+    assert!(driver.insert(9, Value(vec![9])).is_err());
+    driver.insert(25, Value(vec![25])).unwrap();
+    assert_eq!(
+        driver.advance(30),
+        vec![(10, 20, Value(vec![10, 12])), (20, 30, Value(vec![25]))]
+    );
+    assert_eq!(driver.watermark(), Some(30));
+    assert_eq!(driver.next_window(), 30..40);
+}
+
+#[test]
+fn test_sliding() {
+    let mut driver = Driver::<Time, Value, Concat, Window, _>::new(Sliding { size: 10, slide: 5 });
+    driver.insert(7, Value(vec![7])).unwrap();
+    driver.insert(2, Value(vec![2])).unwrap();
+    driver.insert(12, Value(vec![12])).unwrap();
+    assert_eq!(driver.advance(10), vec![(0, 10, Value(vec![2, 7]))]);
+    assert!(driver.insert(4, Value(vec![4])).is_err());
+    driver.insert(5, Value(vec![5])).unwrap();
+    assert_eq!(
+        driver.advance(20),
+        vec![(5, 15, Value(vec![5, 7, 12])), (10, 20, Value(vec![12]))]
+    );
+}
+
+#[test]
+fn test_landmark() {
+    let mut driver = Driver::<Time, Value, Concat, Window, _>::new(Landmark { slide: 10 });
+    driver.insert(3, Value(vec![3])).unwrap();
+    assert_eq!(driver.advance(10), vec![(0, 10, Value(vec![3]))]);
+    driver.insert(1, Value(vec![1])).unwrap();
+    driver.insert(15, Value(vec![15])).unwrap();
+    assert_eq!(driver.advance(20), vec![(0, 20, Value(vec![1, 3, 15]))]);
+}
+
+#[test]
+fn test_first() {
+    assert_eq!(Tumbling { size: 10 }.first(0), 0..10);
+    assert_eq!(Tumbling { size: 10 }.first(10), 10..20);
+    assert_eq!(Tumbling { size: 10 }.first(25), 20..30);
+    assert_eq!(Sliding { size: 10, slide: 4 }.first(0), 0..10);
+    assert_eq!(Sliding { size: 10, slide: 4 }.first(10), 4..14);
+    assert_eq!(Sliding { size: 10, slide: 4 }.first(13), 4..14);
+    assert_eq!(Sliding { size: 10, slide: 4 }.first(14), 8..18);
+    assert_eq!(Landmark { slide: 10 }.first(0), 0..10);
+    assert_eq!(Landmark { slide: 10 }.first(25), 0..30);
+}
+
+// Windows are aligned to time zero, but the driver jumps to the window of the
+// first value and over gaps without values instead of querying every window
+#[test]
+fn test_gaps() {
+    let epoch: Time = 1_600_000_000_000;
+    let mut driver = Driver::<Time, Value, Concat, Window, _>::new(Tumbling { size: 10 });
+    driver.insert(epoch + 3, Value(vec![3])).unwrap();
+    assert_eq!(
+        driver.advance(epoch + 10),
+        vec![(epoch, epoch + 10, Value(vec![3]))]
+    );
+    assert_eq!(driver.next_window(), epoch + 10..epoch + 20);
+    driver.insert(epoch + 1_000_005, Value(vec![5])).unwrap();
+    assert_eq!(driver.advance(epoch + 1_000_000), vec![]);
+    assert_eq!(driver.next_window(), epoch + 1_000_000..epoch + 1_000_010);
+    assert_eq!(
+        driver.advance(epoch + 2_000_000),
+        vec![(epoch + 1_000_000, epoch + 1_000_010, Value(vec![5]))]
+    );
+    assert_eq!(driver.next_window(), epoch + 2_000_000..epoch + 2_000_010);
+
+    let mut driver =
+        Driver::<Time, Value, Concat, Window, _>::with_lateness(Sliding { size: 10, slide: 5 }, 20);
+    driver.insert(epoch + 7, Value(vec![7])).unwrap();
+    assert_eq!(
+        driver.advance(epoch + 100),
+        vec![
+            (epoch, epoch + 10, Value(vec![7])),
+            (epoch + 5, epoch + 15, Value(vec![7])),
+        ]
+    );
+    // Windows without values within the lateness may still be updated
+    assert_eq!(
+        driver.insert(epoch + 82, Value(vec![82])).unwrap(),
+        vec![
+            (epoch + 75, epoch + 85, Value(vec![82])),
+            (epoch + 80, epoch + 90, Value(vec![82])),
+        ]
+    );
+    assert!(driver.insert(epoch + 74, Value(vec![74])).is_err());
+}
+
+#[test]
+fn test_lateness() {
+    let mut driver =
+        Driver::<Time, Value, Concat, Window, _>::with_lateness(Tumbling { size: 10 }, 5);
+    driver.insert(3, Value(vec![3])).unwrap();
+    driver.insert(12, Value(vec![12])).unwrap();
+    assert_eq!(driver.advance(10), vec![(0, 10, Value(vec![3]))]);
+    // The first window is updated until the watermark passes 15
+    assert_eq!(
+        driver.insert(7, Value(vec![7])).unwrap(),
+        vec![(0, 10, Value(vec![3, 7]))]
+    );
+    assert_eq!(driver.advance(15), vec![]);
+    let error = driver.insert(8, Value(vec![8])).unwrap_err();
+    assert_eq!(error.time, 8);
+    assert_eq!(error.value, Value(vec![8]));
+    // Late for the watermark, but the window has not been emitted
+    assert_eq!(driver.insert(11, Value(vec![11])).unwrap(), vec![]);
+    assert_eq!(driver.advance(20), vec![(10, 20, Value(vec![11, 12]))]);
+    assert_eq!(
+        driver.insert(19, Value(vec![19])).unwrap(),
+        vec![(10, 20, Value(vec![11, 12, 19]))]
+    );
+    assert_eq!(driver.late(), 3);
+    assert_eq!(driver.dropped(), 1);
+}
+
+// Inserts values up to 40 time units later than the lateness allows, and checks
+// that the last result of each window contains the values which were inserted
+// before the watermark passed its end plus `lateness`
+fn test_random_lateness<Assigner>(assigner: Assigner, lateness: Time)
+where
+    Assigner: WindowAssigner<Time>,
+{
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut driver =
+        Driver::<Time, Value, Concat, Window, Assigner>::with_lateness(assigner, lateness);
+    let disorder = lateness + 40;
+    let mut values = Vec::new();
+    let mut results = BTreeMap::new();
+    let mut dropped = 0;
+    let mut watermark: Time = 0;
+    for i in 0..1000 {
+        watermark += rng.gen_range(0, 3);
+        let time = (watermark + 5).saturating_sub(rng.gen_range(0, disorder));
+        match driver.insert(time, Value(vec![i])) {
+            Ok(updates) => {
+                values.push((time, i, driver.watermark()));
+                // Windows which had no values are emitted for the first time
+                results.extend(updates.into_iter().map(|(s, e, v)| ((s, e), v)));
+            }
+            Err(_) => dropped += 1,
+        }
+        for (start, end, agg) in driver.advance(watermark) {
+            assert!(results.insert((start, end), agg).is_none());
+        }
+    }
+    assert!(dropped > 0);
+    assert_eq!(driver.dropped(), dropped);
+    assert!(driver.late() > 0);
+    for ((start, end), agg) in results {
+        let values = values
+            .iter()
+            .filter(|(_, _, watermark)| *watermark < Some(end + lateness))
+            .map(|(t, v, _)| (*t, *v))
+            .collect::<Vec<_>>();
+        assert_eq!(agg, naive(&values, start, end));
+    }
+}
+
+#[test]
+fn test_random_lateness_tumbling() {
+    for &lateness in [0, 5, 50].iter() {
+        test_random_lateness(Tumbling { size: 7 }, lateness);
+    }
+}
+
+#[test]
+fn test_random_lateness_sliding() {
+    for &lateness in [0, 5, 50].iter() {
+        test_random_lateness(Sliding { size: 30, slide: 4 }, lateness);
+    }
+}
+
+// FIBA which rejects values more than 10 time units older than the newest one
+struct Bounded {
+    fiba: Window,
+    newest: Option<Time>,
+}
+
+impl TimeWindow<Time, Value, Concat> for Bounded {
+    fn new() -> Self {
+        Self {
+            fiba: Window::new(),
+            newest: None,
+        }
+    }
+    fn insert(&mut self, t: Time, v: Value) -> Result<(), OutOfOrder<Time, Value>> {
+        if matches!(self.newest, Some(newest) if t + 10 < newest) {
+            return Err(OutOfOrder { time: t, value: v });
+        }
+        self.newest = self.newest.max(Some(t));
+        TimeWindow::insert(&mut self.fiba, t, v)
+    }
+    fn evict(&mut self, t: Time) {
+        TimeWindow::evict(&mut self.fiba, t)
+    }
+    fn query(&self) -> Value {
+        TimeWindow::query(&self.fiba)
+    }
+    fn oldest(&self) -> Option<Time> {
+        TimeWindow::oldest(&self.fiba)
+    }
+}
+
+impl TimeWindowExtra<Time, Value, Concat> for Bounded {
+    fn range_query(&mut self, range: std::ops::Range<Time>) -> Value {
+        TimeWindowExtra::range_query(&mut self.fiba, range)
+    }
+    fn oldest_since(&self, t: Time) -> Option<Time> {
+        self.fiba.oldest_since(t)
+    }
+}
+
+// Values which the time window rejects are dropped as well
+#[test]
+fn test_rejected() {
+    let mut driver =
+        Driver::<Time, Value, Concat, Bounded, _>::with_lateness(Tumbling { size: 10 }, 50);
+    driver.insert(25, Value(vec![25])).unwrap();
+    assert_eq!(driver.insert(5, Value(vec![5])).unwrap_err().time, 5);
+    assert_eq!(
+        driver.insert_changes(6, Value(vec![6])).unwrap_err().time,
+        6
+    );
+    driver.insert(20, Value(vec![20])).unwrap();
+    assert_eq!(driver.dropped(), 2);
+    assert_eq!(driver.advance(30), vec![(20, 30, Value(vec![20, 25]))]);
+}
+
+#[test]
+fn test_changes() {
+    let mut driver =
+        Driver::<Time, Value, Concat, Window, _>::with_lateness(Sliding { size: 10, slide: 5 }, 10);
+    driver.insert_changes(3, Value(vec![3])).unwrap();
+    driver.insert_changes(8, Value(vec![8])).unwrap();
+    assert_eq!(
+        driver.advance_changes(10),
+        vec![Change::Insert(0, 10, Value(vec![3, 8]))]
+    );
+    assert_eq!(
+        driver.advance_changes(15),
+        vec![Change::Insert(5, 15, Value(vec![8]))]
+    );
+    // A late value updates both windows which contain it
+    assert_eq!(
+        driver.insert_changes(6, Value(vec![6])).unwrap(),
+        vec![
+            Change::Retract(0, 10, Value(vec![3, 8])),
+            Change::Insert(0, 10, Value(vec![3, 6, 8])),
+            Change::Retract(5, 15, Value(vec![8])),
+            Change::Insert(5, 15, Value(vec![6, 8])),
+        ]
+    );
+    // The first window can no longer be updated, and 10..20 has no values
+    assert_eq!(driver.advance_changes(20), vec![]);
+    assert_eq!(
+        driver.insert_changes(7, Value(vec![7])).unwrap(),
+        vec![
+            Change::Retract(5, 15, Value(vec![6, 8])),
+            Change::Insert(5, 15, Value(vec![6, 7, 8])),
+        ]
+    );
+    // There is nothing to retract for a window which was not emitted
+    assert_eq!(
+        driver.insert_changes(12, Value(vec![12])).unwrap(),
+        vec![
+            Change::Retract(5, 15, Value(vec![6, 7, 8])),
+            Change::Insert(5, 15, Value(vec![6, 7, 8, 12])),
+            Change::Insert(10, 20, Value(vec![12])),
+        ]
+    );
+    assert!(driver.insert_changes(4, Value(vec![4])).is_err());
+}
+
+// Applies the changes of a driver to a table of windows, and checks that the
+// table ends up with the last results of a driver without retractions
+#[test]
+fn test_random_changes() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let assigner = Sliding { size: 30, slide: 4 };
+    let mut driver = Driver::<Time, Value, Concat, Window, _>::with_lateness(assigner, 10);
+    let mut changes = Driver::<Time, Value, Concat, Window, _>::with_lateness(assigner, 10);
+    let mut results = BTreeMap::new();
+    let mut table = BTreeMap::new();
+    let mut apply = |change| match change {
+        Change::Retract(start, end, agg) => assert_eq!(table.remove(&(start, end)), Some(agg)),
+        Change::Insert(start, end, agg) => assert!(table.insert((start, end), agg).is_none()),
+    };
+    let mut watermark: Time = 0;
+    for i in 0..1000 {
+        watermark += rng.gen_range(0, 3);
+        let time = (watermark + 5).saturating_sub(rng.gen_range(0, 60));
+        match (
+            driver.insert(time, Value(vec![i])),
+            changes.insert_changes(time, Value(vec![i])),
+        ) {
+            (Ok(updates), Ok(updates_changes)) => {
+                results.extend(updates.into_iter().map(|(s, e, v)| ((s, e), v)));
+                updates_changes.into_iter().for_each(&mut apply);
+            }
+            (Err(_), Err(_)) => {}
+            _ => panic!("Both drivers must accept or drop the same values"),
+        }
+        results.extend(
+            driver
+                .advance(watermark)
+                .into_iter()
+                .map(|(s, e, v)| ((s, e), v)),
+        );
+        changes
+            .advance_changes(watermark)
+            .into_iter()
+            .for_each(&mut apply);
+    }
+    assert!(!results.is_empty());
+    assert_eq!(table, results);
+}
+
+#[test]
+fn test_random_tumbling() {
+    test_random(Tumbling { size: 7 }, (0..).map(|k| (k * 7, k * 7 + 7)));
+}
+
+#[test]
+fn test_random_sliding() {
+    test_random(
+        Sliding { size: 30, slide: 4 },
+        (0..).map(|k| (k * 4, k * 4 + 30)),
+    );
+}
+
+#[test]
+fn test_random_landmark() {
+    test_random(Landmark { slide: 50 }, (1..).map(|k| (0, k * 50)));
+}
+
+#[test]
+fn test_sessions() {
+    let mut sessions = Sessions::<Time, Value, Concat, Window>::new(5);
+    sessions.insert(1, Value(vec![1])).unwrap();
+    sessions.insert(4, Value(vec![4])).unwrap();
+    sessions.insert(12, Value(vec![12])).unwrap();
+    sessions.insert(20, Value(vec![20])).unwrap();
+    assert_eq!(
+        sessions.sessions().collect::<Vec<_>>(),
+        vec![(1, 4), (12, 12), (20, 20)]
+    );
+    // The watermark has not passed 4 + 5
+    assert_eq!(sessions.advance(9), vec![]);
+    // A late value bridges the gap between two sessions
+    sessions.insert(16, Value(vec![16])).unwrap();
+    assert_eq!(
+        sessions.sessions().collect::<Vec<_>>(),
+        vec![(1, 4), (12, 20)]
+    );
+    assert_eq!(sessions.advance(10), vec![(1, 9, Value(vec![1, 4]))]);
+    assert!(sessions.insert(9, Value(vec![9])).is_err());
+    // A late value extends the start of a session
+    sessions.insert(10, Value(vec![10])).unwrap();
+    assert_eq!(
+        sessions.advance(30),
+        vec![(10, 25, Value(vec![10, 12, 16, 20]))]
+    );
+    assert_eq!(sessions.sessions().count(), 0);
+    assert_eq!(sessions.watermark(), Some(30));
+}
+
+// A session would not contain its last value
+#[test]
+#[should_panic]
+fn test_sessions_zero_gap() {
+    Sessions::<Time, Value, Concat, Window>::new(0);
+}
+
+#[test]
+#[should_panic]
+fn test_slicing_sessions_zero_gap() {
+    Slicing::<Time, Value, Concat>::new().add_session_query(0);
+}
+
+#[test]
+fn test_random_sessions() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let gap = 4;
+    let disorder = 20;
+    let mut sessions = Sessions::<Time, Value, Concat, Window>::new(gap);
+    let mut values = Vec::new();
+    let mut results = Vec::new();
+    // The first value is not the oldest
+    let mut newest: Time = 1000;
+    for i in 0..1000 {
+        newest += rng.gen_range(0, 4);
+        let time = newest.saturating_sub(rng.gen_range(0, disorder));
+        sessions.insert(time, Value(vec![i])).unwrap();
+        values.push((time, i));
+        results.extend(sessions.advance(newest.saturating_sub(disorder)));
+    }
+    results.extend(sessions.advance(newest + gap + 1));
+    // Splits the values at gaps longer than the gap
+    let mut times = values.iter().map(|(t, _)| *t).collect::<Vec<_>>();
+    times.sort();
+    let mut expected = Vec::new();
+    let mut start = times[0];
+    for pair in times.windows(2) {
+        if pair[1] > pair[0] + gap {
+            expected.push((start, pair[0] + gap, naive(&values, start, pair[0] + gap)));
+            start = pair[1];
+        }
+    }
+    let last = *times.last().unwrap();
+    expected.push((start, last + gap, naive(&values, start, last + gap)));
+    assert!(expected.len() > 1);
+    assert_eq!(results, expected);
+}
+
+// Addition, which is commutative
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Sum(i64);
+
+#[derive(Copy, Clone, Debug)]
+struct Plus;
+
+impl Operator for Plus {
+    fn operator_token() -> Plus {
+        Plus
+    }
+}
+
+impl Identity<Plus> for Sum {
+    fn identity() -> Sum {
+        Sum(0)
+    }
+}
+
+impl AbstractMagma<Plus> for Sum {
+    fn operate(&self, other: &Self) -> Self {
+        Sum(self.0 + other.0)
+    }
+}
+
+impl AbstractSemigroup<Plus> for Sum {}
+impl AbstractMonoid<Plus> for Sum {}
+
+#[test]
+fn test_slicing() {
+    let mut slicing = Slicing::<Time, Value, Concat>::new();
+    let tumbling = slicing.add_query(Tumbling { size: 10 });
+    let sliding = slicing.add_query(Sliding { size: 10, slide: 5 });
+    let sessions = slicing.add_session_query(3);
+    slicing.insert(1, Value(vec![1])).unwrap();
+    slicing.insert(2, Value(vec![2])).unwrap();
+    slicing.insert(8, Value(vec![8])).unwrap();
+    slicing.insert(12, Value(vec![12])).unwrap();
+    // Slices are shared by all queries
+    assert_eq!(
+        slicing.edges().collect::<Vec<_>>(),
+        vec![0, 1, 5, 8, 10, 11, 12, 15, 20]
+    );
+    assert_eq!(
+        slicing.advance(12),
+        vec![
+            (tumbling, 0, 10, Value(vec![1, 2, 8])),
+            (sliding, 0, 10, Value(vec![1, 2, 8])),
+            (sessions, 1, 5, Value(vec![1, 2])),
+            (sessions, 8, 11, Value(vec![8])),
+        ]
+    );
+    assert!(slicing.insert(11, Value(vec![11])).is_err());
+    // A late value bridges the gap between two sessions
+    slicing.insert(17, Value(vec![17])).unwrap();
+    slicing.insert(14, Value(vec![14])).unwrap();
+    assert_eq!(
+        slicing.advance(30),
+        vec![
+            (tumbling, 10, 20, Value(vec![12, 14, 17])),
+            (sliding, 5, 15, Value(vec![8, 12, 14])),
+            (sliding, 10, 20, Value(vec![12, 14, 17])),
+            (sliding, 15, 25, Value(vec![17])),
+            (sessions, 12, 20, Value(vec![12, 14, 17])),
+        ]
+    );
+}
+
+// Queries start with the window of the first value, and skip the edges and
+// queries of windows without values
+#[test]
+fn test_slicing_gaps() {
+    let epoch: Time = 1_600_000_000_000;
+    let mut slicing = Slicing::<Time, Value, Concat>::new();
+    let tumbling = slicing.add_query(Tumbling { size: 10 });
+    let sliding = slicing.add_query(Sliding { size: 10, slide: 5 });
+    slicing.insert(epoch + 7, Value(vec![7])).unwrap();
+    assert_eq!(
+        slicing.edges().collect::<Vec<_>>(),
+        vec![epoch, epoch + 5, epoch + 10, epoch + 15]
+    );
+    assert_eq!(
+        slicing.advance(epoch + 1_000_000),
+        vec![
+            (tumbling, epoch, epoch + 10, Value(vec![7])),
+            (sliding, epoch, epoch + 10, Value(vec![7])),
+            (sliding, epoch + 5, epoch + 15, Value(vec![7])),
+        ]
+    );
+    slicing.insert(epoch + 1_000_003, Value(vec![3])).unwrap();
+    assert_eq!(
+        slicing.edges().collect::<Vec<_>>(),
+        vec![
+            epoch + 15,
+            epoch + 999_995,
+            epoch + 1_000_000,
+            epoch + 1_000_005,
+            epoch + 1_000_010,
+        ]
+    );
+    assert_eq!(
+        slicing.advance(epoch + 2_000_000),
+        vec![
+            (
+                tumbling,
+                epoch + 1_000_000,
+                epoch + 1_000_010,
+                Value(vec![3])
+            ),
+            (sliding, epoch + 999_995, epoch + 1_000_005, Value(vec![3])),
+            (
+                sliding,
+                epoch + 1_000_000,
+                epoch + 1_000_010,
+                Value(vec![3])
+            ),
+        ]
+    );
+    // Before the first value, the windows start after the watermark
+    let mut slicing = Slicing::<Time, Value, Concat>::new();
+    let tumbling = slicing.add_query(Tumbling { size: 10 });
+    assert_eq!(slicing.advance(epoch + 15), vec![]);
+    slicing.insert(epoch + 25, Value(vec![25])).unwrap();
+    assert_eq!(
+        slicing.edges().collect::<Vec<_>>(),
+        vec![epoch + 10, epoch + 20, epoch + 30]
+    );
+    assert_eq!(
+        slicing.advance(epoch + 30),
+        vec![(tumbling, epoch + 20, epoch + 30, Value(vec![25]))]
+    );
+}
+
+#[test]
+fn test_slicing_older_than_first() {
+    let mut slicing = Slicing::<Time, Value, Concat>::new();
+    let tumbling = slicing.add_query(Tumbling { size: 10 });
+    let sliding = slicing.add_query(Sliding { size: 10, slide: 5 });
+    let landmark = slicing.add_query(Landmark { slide: 10 });
+    slicing.insert(55, Value(vec![55])).unwrap();
+    assert_eq!(slicing.edges().collect::<Vec<_>>(), vec![0, 50, 55, 60, 65]);
+    // The windows of 45 are added before those of 55
+    slicing.insert(45, Value(vec![45])).unwrap();
+    assert_eq!(
+        slicing.edges().collect::<Vec<_>>(),
+        vec![0, 40, 45, 50, 55, 60, 65]
+    );
+    slicing.insert(32, Value(vec![32])).unwrap();
+    assert_eq!(
+        slicing.advance(100),
+        vec![
+            (tumbling, 30, 40, Value(vec![32])),
+            (tumbling, 40, 50, Value(vec![45])),
+            (tumbling, 50, 60, Value(vec![55])),
+            (sliding, 25, 35, Value(vec![32])),
+            (sliding, 30, 40, Value(vec![32])),
+            (sliding, 40, 50, Value(vec![45])),
+            (sliding, 45, 55, Value(vec![45])),
+            (sliding, 50, 60, Value(vec![55])),
+            (sliding, 55, 65, Value(vec![55])),
+            (landmark, 0, 40, Value(vec![32])),
+            (landmark, 0, 50, Value(vec![32, 45])),
+            (landmark, 0, 60, Value(vec![32, 45, 55])),
+            (landmark, 0, 70, Value(vec![32, 45, 55])),
+            (landmark, 0, 80, Value(vec![32, 45, 55])),
+            (landmark, 0, 90, Value(vec![32, 45, 55])),
+            (landmark, 0, 100, Value(vec![32, 45, 55])),
+        ]
+    );
+}
+
+#[test]
+fn test_random_slicing() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let gap = 4;
+    let disorder = 20;
+    let mut slicing = Slicing::<Time, Sum, Plus>::new();
+    slicing.add_query(Tumbling { size: 7 });
+    slicing.add_query(Sliding { size: 30, slide: 4 });
+    slicing.add_query(Landmark { slide: 50 });
+    slicing.add_session_query(gap);
+    let mut values = Vec::new();
+    let mut results = Vec::new();
+    // The first value is not the oldest
+    let mut newest: Time = 1000;
+    for i in 0..1000 {
+        newest += rng.gen_range(0, 4);
+        let time = newest.saturating_sub(rng.gen_range(0, disorder));
+        slicing.insert(time, Sum(i)).unwrap();
+        values.push((time, i));
+        results.extend(slicing.advance(newest.saturating_sub(disorder)));
+    }
+    let watermark = newest + gap + 1;
+    results.extend(slicing.advance(watermark));
+    let sum = |start: Time, end: Time| {
+        let sum = values
+            .iter()
+            .filter(|(t, _)| start <= *t && *t < end)
+            .map(|(_, v)| *v)
+            .sum();
+        Sum(sum)
+    };
+    // Results of each query are emitted in order
+    let windows = |id: usize| {
+        results
+            .iter()
+            .filter(|(query, ..)| *query == id)
+            .map(|(_, start, end, agg)| (*start, *end, *agg))
+            .collect::<Vec<_>>()
+    };
+    // Windows without values are not emitted
+    let expected = |windows: &mut dyn Iterator<Item = (Time, Time)>| {
+        windows
+            .take_while(|(_, end)| *end <= watermark)
+            .filter(|(start, end)| values.iter().any(|(t, _)| start <= t && t < end))
+            .map(|(start, end)| (start, end, sum(start, end)))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(windows(0), expected(&mut (0..).map(|k| (k * 7, k * 7 + 7))));
+    assert_eq!(
+        windows(1),
+        expected(&mut (0..).map(|k| (k * 4, k * 4 + 30)))
+    );
+    assert_eq!(windows(2), expected(&mut (1..).map(|k| (0, k * 50))));
+    // Splits the values at gaps longer than the gap
+    let mut times = values.iter().map(|(t, _)| *t).collect::<Vec<_>>();
+    times.sort();
+    let mut sessions = Vec::new();
+    let mut start = times[0];
+    for pair in times.windows(2) {
+        if pair[1] > pair[0] + gap {
+            sessions.push((start, pair[0] + gap));
+            start = pair[1];
+        }
+    }
+    sessions.push((start, times.last().unwrap() + gap));
+    assert_eq!(windows(3), expected(&mut sessions.into_iter()));
+}
+
+fn test_delta<Window>()
+where
+    Window: TimeWindow<Time, Value, Concat>,
+{
+    let assigner = Delta {
+        delta: 5,
+        key: |v: &Value| v.0[0],
+    };
+    let mut windows = DataDriven::<Time, Value, Concat, Window, _>::new(assigner);
+    assert_eq!(windows.insert(1, Value(vec![10])).unwrap(), None);
+    assert_eq!(windows.insert(2, Value(vec![13])).unwrap(), None);
+    // 16 is more than 5 from 10, the first value of the window
+    assert_eq!(
+        windows.insert(3, Value(vec![16])).unwrap(),
+        Some((1, 3, Value(vec![10, 13])))
+    );
+    assert_eq!(windows.insert(3, Value(vec![12])).unwrap(), None);
+    assert_eq!(windows.query(), Value(vec![16, 12]));
+    assert!(windows.insert(2, Value(vec![14])).is_err());
+    assert_eq!(
+        windows.insert(5, Value(vec![22])).unwrap(),
+        Some((3, 5, Value(vec![16, 12])))
+    );
+    assert_eq!(windows.flush(), Some((5, 5, Value(vec![22]))));
+    assert_eq!(windows.flush(), None);
+}
+
+fn test_punctuation<Window>()
+where
+    Window: TimeWindow<Time, Value, Concat>,
+{
+    // Empty values are markers
+    let assigner = Punctuation {
+        is_marker: |v: &Value| v.0.is_empty(),
+    };
+    let mut windows = DataDriven::<Time, Value, Concat, Window, _>::new(assigner);
+    assert_eq!(windows.insert(1, Value(vec![1])).unwrap(), None);
+    assert_eq!(windows.insert(2, Value(vec![2])).unwrap(), None);
+    assert_eq!(
+        windows.insert(3, Value(vec![])).unwrap(),
+        Some((1, 3, Value(vec![1, 2])))
+    );
+    // No window is open
+    assert_eq!(windows.insert(3, Value(vec![])).unwrap(), None);
+    assert_eq!(windows.insert(4, Value(vec![4])).unwrap(), None);
+    assert_eq!(windows.insert(4, Value(vec![5])).unwrap(), None);
+    assert_eq!(
+        windows.insert(6, Value(vec![])).unwrap(),
+        Some((4, 6, Value(vec![4, 5])))
+    );
+    assert_eq!(windows.flush(), None);
+}
+
+// FIBA which rejects negative values
+struct NonNegative(Window);
+
+impl TimeWindow<Time, Value, Concat> for NonNegative {
+    fn new() -> Self {
+        Self(Window::new())
+    }
+    fn insert(&mut self, t: Time, v: Value) -> Result<(), OutOfOrder<Time, Value>> {
+        if v.0.iter().any(|x| *x < 0) {
+            return Err(OutOfOrder { time: t, value: v });
+        }
+        TimeWindow::insert(&mut self.0, t, v)
+    }
+    fn evict(&mut self, t: Time) {
+        TimeWindow::evict(&mut self.0, t)
+    }
+    fn query(&self) -> Value {
+        TimeWindow::query(&self.0)
+    }
+    fn oldest(&self) -> Option<Time> {
+        TimeWindow::oldest(&self.0)
+    }
+}
+
+// A value which the time window rejects does not close the current window
+#[test]
+fn test_delta_rejected() {
+    let assigner = Delta {
+        delta: 5,
+        key: |v: &Value| v.0[0],
+    };
+    let mut windows = DataDriven::<Time, Value, Concat, NonNegative, _>::new(assigner);
+    assert_eq!(windows.insert(1, Value(vec![10])).unwrap(), None);
+    assert_eq!(windows.insert(2, Value(vec![-10])).unwrap_err().time, 2);
+    assert_eq!(windows.query(), Value(vec![10]));
+    assert_eq!(
+        windows.insert(2, Value(vec![20])).unwrap(),
+        Some((1, 2, Value(vec![10])))
+    );
+    assert_eq!(windows.query(), Value(vec![20]));
+    // The next window starts at the same time as a value of the current one
+    assert_eq!(
+        windows.insert(2, Value(vec![30])).unwrap(),
+        Some((2, 2, Value(vec![20])))
+    );
+    assert_eq!(windows.insert(3, Value(vec![-30])).unwrap_err().time, 3);
+    assert_eq!(windows.insert(3, Value(vec![31])).unwrap(), None);
+    assert_eq!(windows.query(), Value(vec![30, 31]));
+    assert_eq!(windows.flush(), Some((2, 3, Value(vec![30, 31]))));
+    assert_eq!(windows.query(), Value(vec![]));
+}
+
+#[test]
+fn test_delta_fiba() {
+    test_delta::<Window>();
+}
+
+#[test]
+fn test_delta_time_fifo() {
+    test_delta::<TimeFifo<Time, Value, Concat, TwoStacks<Value, Concat>>>();
+}
+
+#[test]
+fn test_punctuation_fiba() {
+    test_punctuation::<Window>();
+}
+
+#[test]
+fn test_punctuation_time_fifo() {
+    test_punctuation::<TimeFifo<Time, Value, Concat, TwoStacks<Value, Concat>>>();
+}